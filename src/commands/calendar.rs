@@ -0,0 +1,225 @@
+use serenity::{
+    builder::CreateApplicationCommand,
+    model::prelude::{
+        command::CommandOptionType,
+        interaction::application_command::{
+            ApplicationCommandInteraction, CommandDataOption, CommandDataOptionValue,
+        },
+        ReactionType,
+    },
+};
+
+use crate::commands::handler::{is_admin, Response};
+use crate::friday::calendar::{
+    get_themed_days, parse_date, todays_themes, Recurrence, ThemedDay, CALENDAR,
+};
+
+pub async fn run(
+    options: &[CommandDataOption],
+    command: &ApplicationCommandInteraction,
+) -> Response {
+    let subcommand = match options.first() {
+        Some(subcommand) => subcommand,
+        None => return Response::Hidden("Please provide a subcommand".to_string()),
+    };
+    match subcommand.name.as_str() {
+        "list" => list().await,
+        "today" => today().await,
+        "add" => add(&subcommand.options, command).await,
+        "remove" => remove(&subcommand.options, command).await,
+        _ => Response::Hidden("Unknown subcommand".to_string()),
+    }
+}
+
+fn describe(day: &ThemedDay) -> String {
+    let recurrence = match day.recurrence {
+        Recurrence::Once => "once",
+        Recurrence::Weekly => "weekly",
+        Recurrence::Yearly => "yearly",
+    };
+    let reaction = day.reaction.clone().unwrap_or_default();
+    format!("{} {} ({}) {}", day.date, day.name, recurrence, reaction)
+}
+
+async fn list() -> Response {
+    let days = get_themed_days().await;
+    if days.is_empty() {
+        return Response::Hidden("There are no themed days".to_string());
+    }
+    let list = days
+        .iter()
+        .map(describe)
+        .collect::<Vec<String>>()
+        .join("\n");
+    Response::Hidden(format!("Themed days:\n{}", list))
+}
+
+async fn today() -> Response {
+    let days = todays_themes().await;
+    if days.is_empty() {
+        return Response::Hidden("Today is just a normal day".to_string());
+    }
+    let list = days
+        .iter()
+        .map(describe)
+        .collect::<Vec<String>>()
+        .join("\n");
+    Response::Hidden(format!("Today is:\n{}", list))
+}
+
+async fn add(options: &[CommandDataOption], command: &ApplicationCommandInteraction) -> Response {
+    if !is_admin(command.user.id.0) {
+        return Response::Hidden("You are not an admin".to_string());
+    }
+    let mut day = ThemedDay {
+        name: String::new(),
+        date: String::new(),
+        recurrence: Recurrence::Once,
+        announcement: String::new(),
+        reaction: None,
+    };
+    for option in options {
+        if let Some(CommandDataOptionValue::String(value)) = option.resolved.as_ref() {
+            match option.name.as_str() {
+                "name" => day.name = value.to_string(),
+                "date" => day.date = value.to_string(),
+                "recurrence" => {
+                    day.recurrence = match value.as_str() {
+                        "weekly" => Recurrence::Weekly,
+                        "yearly" => Recurrence::Yearly,
+                        _ => Recurrence::Once,
+                    }
+                }
+                "announcement" => day.announcement = value.to_string(),
+                "reaction" => day.reaction = Some(value.to_string()),
+                _ => {}
+            }
+        }
+    }
+    if parse_date(&day.date).is_none() {
+        return Response::Hidden("Please provide a date formatted as YYYY-MM-DD".to_string());
+    }
+    if let Some(reaction) = &day.reaction {
+        if ReactionType::try_from(reaction.as_str()).is_err() {
+            return Response::Hidden("Please provide a valid reaction".to_string());
+        }
+    }
+    if day.announcement.is_empty() {
+        day.announcement = format!("Happy {}!", day.name);
+    }
+    let description = describe(&day);
+    let added = CALENDAR
+        .update(|calendar| {
+            if calendar.iter().any(|d| d.name == day.name) {
+                return false;
+            }
+            calendar.push(day);
+            true
+        })
+        .await;
+    if !added {
+        return Response::Hidden("A themed day by that name already exists".to_string());
+    }
+    Response::Shown(format!("Added themed day {}", description))
+}
+
+async fn remove(
+    options: &[CommandDataOption],
+    command: &ApplicationCommandInteraction,
+) -> Response {
+    if !is_admin(command.user.id.0) {
+        return Response::Hidden("You are not an admin".to_string());
+    }
+    if let Some(CommandDataOptionValue::String(name)) =
+        options.first().and_then(|option| option.resolved.as_ref())
+    {
+        let removed = CALENDAR
+            .update(|calendar| {
+                let len = calendar.len();
+                calendar.retain(|day| day.name != *name);
+                calendar.len() != len
+            })
+            .await;
+        if !removed {
+            return Response::Hidden("No themed day by that name exists".to_string());
+        }
+        Response::Shown(format!("Removed themed day {}", name))
+    } else {
+        Response::Hidden("Please provide a valid name".to_string())
+    }
+}
+
+pub fn register(command: &mut CreateApplicationCommand) -> &mut CreateApplicationCommand {
+    command
+        .name("calendar")
+        .description("Themed days like Meme Monday and holidays")
+        .create_option(|option| {
+            option
+                .name("list")
+                .description("List all themed days")
+                .kind(CommandOptionType::SubCommand)
+        })
+        .create_option(|option| {
+            option
+                .name("today")
+                .description("Show the themed days today")
+                .kind(CommandOptionType::SubCommand)
+        })
+        .create_option(|option| {
+            option
+                .name("add")
+                .description("Add a themed day")
+                .kind(CommandOptionType::SubCommand)
+                .create_sub_option(|option| {
+                    option
+                        .name("name")
+                        .description("Name of the day")
+                        .kind(CommandOptionType::String)
+                        .required(true)
+                })
+                .create_sub_option(|option| {
+                    option
+                        .name("date")
+                        .description("First occurrence, formatted as YYYY-MM-DD")
+                        .kind(CommandOptionType::String)
+                        .required(true)
+                })
+                .create_sub_option(|option| {
+                    option
+                        .name("recurrence")
+                        .description("Defaults to once")
+                        .kind(CommandOptionType::String)
+                        .required(false)
+                        .add_string_choice("Once", "once")
+                        .add_string_choice("Weekly", "weekly")
+                        .add_string_choice("Yearly", "yearly")
+                })
+                .create_sub_option(|option| {
+                    option
+                        .name("announcement")
+                        .description("Message posted when the day starts")
+                        .kind(CommandOptionType::String)
+                        .required(false)
+                })
+                .create_sub_option(|option| {
+                    option
+                        .name("reaction")
+                        .description("Emoji to react with on the day")
+                        .kind(CommandOptionType::String)
+                        .required(false)
+                })
+        })
+        .create_option(|option| {
+            option
+                .name("remove")
+                .description("Remove a themed day")
+                .kind(CommandOptionType::SubCommand)
+                .create_sub_option(|option| {
+                    option
+                        .name("name")
+                        .description("Name of the day")
+                        .kind(CommandOptionType::String)
+                        .required(true)
+                })
+        })
+}
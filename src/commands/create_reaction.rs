@@ -1,126 +1,160 @@
-use std::fs::{File, OpenOptions};
+use std::fs::File;
 use std::io::Write;
 
-extern crate base64;
-
+use lazy_static::lazy_static;
 use serde::{Deserialize, Serialize};
 use serenity::builder::CreateApplicationCommand;
 use serenity::model::prelude::command::CommandOptionType;
 use serenity::model::prelude::interaction::application_command::{
     ApplicationCommandInteraction, CommandDataOption, CommandDataOptionValue,
 };
-use serenity::model::prelude::GuildId;
 use serenity::prelude::Context;
 
-use crate::commands::handler::Response;
-use crate::commands::react::Emote;
+use crate::commands::emotes::dupes::find_similar;
+use crate::commands::emotes::proposals::submit;
+use crate::commands::emotes::storage::pick_guild;
+use crate::commands::handler::{is_admin, Response};
+use crate::commands::react::{Emote, EMOTES};
+use crate::imaging::emote::{prepare_emote, ProcessedImage};
+use crate::imaging::{download_image, process};
 
 #[derive(Deserialize, Serialize, Debug)]
-struct HomeChannel {
-    guild_id: u64,
-    channel_id: u64,
+pub struct HomeChannel {
+    pub guild_id: u64,
+    pub channel_id: u64,
 }
 
-pub async fn run(
-    options: &[CommandDataOption],
-    ctx: &Context,
-    command: &ApplicationCommandInteraction,
-) -> Response {
-    let user = command.member.as_ref().unwrap().user.id.0;
-    let file = File::open("data/admin_users.json");
-    if let Ok(file) = file {
-        let admin_users: Vec<u64> = serde_json::from_reader(&file).expect("Unable to read file");
-        if !admin_users.contains(&user) {
-            return Response::Hidden("You are not an admin".to_string());
-        }
-    } else {
-        return Response::Hidden("You are not an admin".to_string());
-    }
-    // Open the emote file and read all emotes into a mutable vector
-    let file = File::open("data/emotes.json").expect("Unable to open file");
-    let mut emotes: Vec<Emote> = serde_json::from_reader(&file).expect("Unable to read file");
-    // Get the home channel from a file
+lazy_static! {
+    /// The home channel file is only edited by hand, so it is read once
+    static ref HOME_CHANNEL: HomeChannel = read_home_channel();
+}
+
+fn read_home_channel() -> HomeChannel {
     let file = File::open("data/home_channel.json");
-    let home_channel = match file {
-        Ok(_) => {
-            let file = File::open("data/home_channel.json").expect("Unable to open file");
-            let home_channel: HomeChannel =
-                serde_json::from_reader(file).expect("Unable to read file");
-            home_channel
-        }
+    match file {
+        Ok(file) => serde_json::from_reader(file).expect("Unable to read file"),
         Err(_) => HomeChannel {
             guild_id: 0,
             channel_id: 0,
         },
-    };
-    // Get the guild of the home channel
-    let guild_id = GuildId(home_channel.guild_id);
-    let guild = guild_id.to_partial_guild(&ctx.http).await.unwrap();
+    }
+}
 
-    // Get the name of the emote
-    let option = options
-        .get(0)
-        .expect("Expected user option")
-        .resolved
-        .as_ref()
-        .expect("Expected user object");
-    let name: String;
-    if let CommandDataOptionValue::String(str) = option {
-        for emote in &emotes {
-            if emote.name == *str {
-                return Response::Hidden("An emote by that name already exists".to_string());
-            }
+pub fn get_home_channel() -> &'static HomeChannel {
+    &HOME_CHANNEL
+}
+
+pub fn valid_name(name: &str) -> bool {
+    (2..=32).contains(&name.len()) && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+async fn check_name(name: &str) -> Result<(), String> {
+    if !valid_name(name) {
+        return Err("Emote names must be 2-32 letters, numbers or underscores".to_string());
+    }
+    if EMOTES
+        .read()
+        .await
+        .iter()
+        .any(|emote| emote.answers_to(name))
+    {
+        return Err("An emote by that name already exists".to_string());
+    }
+    Ok(())
+}
+
+/// Processes the image and uploads it as a new emote, unless it looks like an existing one
+pub async fn create_emote(ctx: &Context, name: &str, bytes: Vec<u8>) -> Result<Emote, String> {
+    check_name(name).await?;
+    let image = process(move || prepare_emote(&bytes)).await?;
+    if let Some(similar) = find_similar(image.hash).await {
+        return Err(format!(
+            "This looks like the existing emote {} '{}'",
+            similar.mention(),
+            similar.name
+        ));
+    }
+    upload_emote(ctx, name, image).await
+}
+
+/// Uploads a processed image as a new emote in a storage guild with room for it
+pub async fn upload_emote(
+    ctx: &Context,
+    name: &str,
+    image: ProcessedImage,
+) -> Result<Emote, String> {
+    check_name(name).await?;
+    let guild_id = pick_guild(ctx, image.animated).await?;
+    let emoji = match guild_id
+        .create_emoji(&ctx.http, name, &image.data_url())
+        .await
+    {
+        Ok(emoji) => emoji,
+        Err(why) => {
+            println!("Unable to create emote: {}", why);
+            return Err(format!("Unable to create the emote: {}", why));
         }
-        name = str.to_string();
-    } else {
-        return Response::Hidden("Please provide a valid name".to_string());
     };
+    let emote = Emote {
+        name: emoji.name,
+        emote: emoji.id.to_string(),
+        guild: Some(guild_id.0),
+        animated: emoji.animated,
+        hash: Some(image.hash),
+        ..Default::default()
+    };
+    // Add the emote to the registry, the emoji update event may have synced it already
+    EMOTES
+        .update(|emotes| {
+            emotes.retain(|e| e.emote != emote.emote);
+            emotes.push(emote.clone());
+        })
+        .await;
+    Ok(emote)
+}
 
-    // Get the emote attachment
-    let option = options
-        .get(1)
-        .expect("Expected user option")
-        .resolved
-        .as_ref()
-        .expect("Expected user object");
+/// Adds the emote if an admin asked for it, everyone else proposes it for review instead
+pub async fn create_or_propose(
+    name: &str,
+    bytes: Vec<u8>,
+    ctx: &Context,
+    command: &ApplicationCommandInteraction,
+) -> Response {
+    if !is_admin(command.user.id.0) {
+        return submit(name, bytes, ctx, command).await;
+    }
+    match create_emote(ctx, name, bytes).await {
+        Ok(emote) => Response::Shown(format!("Added new emote '{}'", emote.name)),
+        Err(why) => Response::Hidden(why),
+    }
+}
 
-    if let CommandDataOptionValue::Attachment(attachment) = option {
-        // Download the image into a buffer
-        let bytes = attachment.download().await.unwrap();
-        // Encode the buffer into a base64 string
-        let b64 = base64::encode(bytes);
-        // Get the file extension, default to jpg
-        let ext = match attachment.filename.split('.').last() {
-            Some(ext) if ext == "png" => ext,
-            _ => "jpg",
-        };
-        // Format the string into a data url
-        let formated = format!("data:image/{};base64,{}", ext, b64);
-        // Create a new emote
-        let emoji = match guild
-            .create_emoji(&ctx.http, name.as_str(), &formated)
-            .await
-        {
-            Ok(emoji) => emoji,
-            Err(_) => return Response::Hidden("Please provide a valid image".to_string()),
-        };
-        // Add the emote to the emote vector
-        emotes.push(Emote {
-            name: emoji.name,
-            emote: emoji.id.to_string(),
-        });
-        // Open the emote file and write the emote vector to it
-        let contents = serde_json::to_string_pretty(&emotes).unwrap();
-        OpenOptions::new()
-            .write(true)
-            .open("data/emotes.json")
-            .unwrap()
-            .write_all(contents.as_bytes())
-            .unwrap();
-        return Response::Shown(format!("Added new emote '{}'", name));
-    } else {
-        return Response::Hidden("Please provide a valid image".to_string());
+pub async fn run(
+    options: &[CommandDataOption],
+    ctx: &Context,
+    command: &ApplicationCommandInteraction,
+) -> Response {
+    let mut name = None;
+    let mut attachment = None;
+    for option in options {
+        match option.resolved.as_ref() {
+            Some(CommandDataOptionValue::String(str)) if option.name == "name" => name = Some(str),
+            Some(CommandDataOptionValue::Attachment(a)) if option.name == "media" => {
+                attachment = Some(a)
+            }
+            _ => {}
+        }
     }
+    let (name, attachment) = match (name, attachment) {
+        (Some(name), Some(attachment)) => (name, attachment),
+        (None, _) => return Response::Hidden("Please provide a valid name".to_string()),
+        (_, None) => return Response::Hidden("Please provide a valid image".to_string()),
+    };
+    let bytes = match download_image(&attachment.url).await {
+        Ok(bytes) => bytes,
+        Err(why) => return Response::Hidden(why),
+    };
+    create_or_propose(name, bytes, ctx, command).await
 }
 
 pub fn register(command: &mut CreateApplicationCommand) -> &mut CreateApplicationCommand {
@@ -143,7 +177,7 @@ pub fn register(command: &mut CreateApplicationCommand) -> &mut CreateApplicatio
     };
     command
         .name("createreaction")
-        .description("Creates a reaction")
+        .description("Creates a reaction, or proposes it if you are not an admin")
         .create_option(|option| {
             option
                 .name("name")
@@ -0,0 +1,84 @@
+use serenity::builder::CreateApplicationCommand;
+use serenity::http::error::Error as HttpError;
+use serenity::model::prelude::command::CommandOptionType;
+use serenity::model::prelude::interaction::application_command::{
+    ApplicationCommandInteraction, CommandDataOption, CommandDataOptionValue,
+};
+use serenity::model::prelude::interaction::message_component::MessageComponentInteraction;
+use serenity::model::prelude::EmojiId;
+use serenity::prelude::{Context, SerenityError};
+
+use crate::commands::handler::{is_admin, Response};
+use crate::commands::react::{find_emote, EMOTES};
+
+pub async fn run(
+    options: &[CommandDataOption],
+    command: &ApplicationCommandInteraction,
+) -> Response {
+    if !is_admin(command.user.id.0) {
+        return Response::Hidden("You are not an admin".to_string());
+    }
+    let option = options.first().and_then(|option| option.resolved.as_ref());
+    if let Some(CommandDataOptionValue::String(name)) = option {
+        let emotes = EMOTES.read().await;
+        match find_emote(&emotes, name) {
+            Some(emote) => Response::Confirm(
+                format!("Delete {} '{}'?", emote.mention(), emote.name),
+                format!("deletereaction:{}", emote.emote),
+            ),
+            None => Response::Hidden("Unable to find emote".to_string()),
+        }
+    } else {
+        Response::Hidden("Please provide a valid emote".to_string())
+    }
+}
+
+fn is_not_found(why: &SerenityError) -> bool {
+    match why {
+        SerenityError::Http(error) => matches!(
+            &**error,
+            HttpError::UnsuccessfulRequest(response) if response.status_code == 404
+        ),
+        _ => false,
+    }
+}
+
+/// Deletes the emote after the confirmation button was pressed
+pub async fn confirm(id: &str, ctx: &Context, component: &MessageComponentInteraction) -> Response {
+    if !is_admin(component.user.id.0) {
+        return Response::Hidden("You are not an admin".to_string());
+    }
+    let emote = match find_emote(&EMOTES.read().await, id) {
+        Some(emote) => emote.clone(),
+        None => return Response::Hidden("Unable to find emote".to_string()),
+    };
+    let emoji_id = match emote.emote.parse::<u64>() {
+        Ok(emoji_id) => EmojiId(emoji_id),
+        Err(_) => return Response::Hidden("Invalid emote id".to_string()),
+    };
+    if let Err(why) = emote.guild_id().delete_emoji(&ctx.http, emoji_id).await {
+        // The emote was already deleted from the server, only the registry is left
+        if !is_not_found(&why) {
+            println!("Unable to delete emote: {}", why);
+            return Response::Hidden("Unable to delete the emote from the server".to_string());
+        }
+    }
+    EMOTES
+        .update(|emotes| emotes.retain(|e| e.emote != emote.emote))
+        .await;
+    Response::Shown(format!("Deleted emote '{}'", emote.name))
+}
+
+pub fn register(command: &mut CreateApplicationCommand) -> &mut CreateApplicationCommand {
+    command
+        .name("deletereaction")
+        .description("Deletes a reaction")
+        .create_option(|option| {
+            option
+                .name("emote")
+                .description("Choose an emote to delete")
+                .kind(CommandOptionType::String)
+                .required(true)
+                .set_autocomplete(true)
+        })
+}
@@ -0,0 +1,100 @@
+use std::collections::BTreeMap;
+
+use crate::commands::emotes::download_emote;
+use crate::commands::handler::{fit_message, Response};
+use crate::commands::react::{Emote, EMOTES};
+use crate::imaging::hash::{hash_distance, image_hash, SIMILAR_DISTANCE};
+use crate::imaging::process;
+
+async fn hash_emote(emote: &Emote) -> Result<u64, String> {
+    let bytes = download_emote(&emote.emote, emote.animated).await?;
+    process(move || image_hash(&bytes)).await
+}
+
+/// Hashes the registry emotes that don't have a hash yet, like ones made before hashing
+/// or added through Discord. Emotes that can't be hashed are marked so they aren't tried again.
+pub async fn backfill_hashes() {
+    let missing: Vec<Emote> = EMOTES
+        .read()
+        .await
+        .iter()
+        .filter(|emote| emote.hash.is_none() && !emote.unhashable)
+        .cloned()
+        .collect();
+    for emote in missing {
+        let hash = hash_emote(&emote).await;
+        if let Err(why) = &hash {
+            println!("Unable to hash emote {}: {}", emote.name, why);
+        }
+        EMOTES
+            .update(|emotes| {
+                if let Some(e) = emotes.iter_mut().find(|e| e.emote == emote.emote) {
+                    e.hash = hash.as_ref().ok().copied();
+                    e.unhashable = hash.is_err();
+                }
+            })
+            .await;
+    }
+}
+
+/// The registry emote that looks the most like the hashed image, if any is close enough
+pub async fn find_similar(hash: u64) -> Option<Emote> {
+    EMOTES
+        .read()
+        .await
+        .iter()
+        .filter_map(|emote| Some((hash_distance(emote.hash?, hash), emote)))
+        .filter(|(distance, _)| *distance <= SIMILAR_DISTANCE)
+        .min_by_key(|(distance, _)| *distance)
+        .map(|(_, emote)| emote.clone())
+}
+
+/// Groups of emotes that look alike, linking emotes through shared neighbours
+fn clusters(emotes: &[Emote]) -> Vec<Vec<&Emote>> {
+    let hashed: Vec<(&Emote, u64)> = emotes
+        .iter()
+        .filter_map(|emote| Some((emote, emote.hash?)))
+        .collect();
+    let mut cluster_of: Vec<usize> = (0..hashed.len()).collect();
+    for i in 0..hashed.len() {
+        for j in i + 1..hashed.len() {
+            if hash_distance(hashed[i].1, hashed[j].1) <= SIMILAR_DISTANCE {
+                let (from, to) = (cluster_of[j], cluster_of[i]);
+                for cluster in cluster_of.iter_mut() {
+                    if *cluster == from {
+                        *cluster = to;
+                    }
+                }
+            }
+        }
+    }
+    let mut clusters: BTreeMap<usize, Vec<&Emote>> = BTreeMap::new();
+    for (index, (emote, _)) in hashed.iter().enumerate() {
+        clusters.entry(cluster_of[index]).or_default().push(emote);
+    }
+    let mut clusters: Vec<Vec<&Emote>> = clusters.into_values().collect();
+    clusters.retain(|cluster| cluster.len() > 1);
+    clusters
+}
+
+pub async fn run() -> Response {
+    let emotes = EMOTES.read().await;
+    let clusters = clusters(&emotes);
+    if clusters.is_empty() {
+        return Response::Hidden("No emotes look alike".to_string());
+    }
+    let lines: Vec<String> = clusters
+        .iter()
+        .map(|cluster| {
+            cluster
+                .iter()
+                .map(|emote| format!("{} {}", emote.mention(), emote.name))
+                .collect::<Vec<String>>()
+                .join(", ")
+        })
+        .collect();
+    Response::Hidden(fit_message(format!(
+        "Emotes that look alike:\n{}",
+        lines.join("\n")
+    )))
+}
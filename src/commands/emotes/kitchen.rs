@@ -0,0 +1,61 @@
+use serenity::model::prelude::interaction::application_command::{
+    ApplicationCommandInteraction, CommandDataOption,
+};
+use serenity::prelude::Context;
+
+use crate::commands::create_reaction::create_or_propose;
+use crate::commands::emotes::{download_emote, string_option};
+use crate::commands::handler::Response;
+use crate::commands::react::{find_emote, Emote, EMOTES};
+use crate::imaging::kitchen::{combine_emotes, Combine};
+use crate::imaging::process;
+
+async fn emote_image(query: &str) -> Result<(Emote, Vec<u8>), String> {
+    let emote = find_emote(&EMOTES.read().await, query)
+        .cloned()
+        .ok_or_else(|| format!("Unable to find emote '{}'", query))?;
+    let bytes = download_emote(&emote.emote, emote.animated).await?;
+    Ok((emote, bytes))
+}
+
+/// Combines two registry emotes, and adds the result as an emote if a name is given
+pub async fn run(
+    options: &[CommandDataOption],
+    ctx: &Context,
+    command: &ApplicationCommandInteraction,
+) -> Response {
+    let combine = match string_option(options, "mode") {
+        Some("overlay") => Combine::Overlay,
+        Some("blend") => Combine::Blend,
+        _ => Combine::SideBySide,
+    };
+    let (first, first_bytes) =
+        match emote_image(string_option(options, "first").unwrap_or_default()).await {
+            Ok(image) => image,
+            Err(why) => return Response::Hidden(why),
+        };
+    let (second, second_bytes) =
+        match emote_image(string_option(options, "second").unwrap_or_default()).await {
+            Ok(image) => image,
+            Err(why) => return Response::Hidden(why),
+        };
+    let combined = process(move || combine_emotes(&first_bytes, &second_bytes, combine)).await;
+    let (data, animated) = match combined {
+        Ok(combined) => combined,
+        Err(why) => return Response::Hidden(why),
+    };
+    if let Some(name) = string_option(options, "name") {
+        return create_or_propose(name, data, ctx, command).await;
+    }
+    Response::File {
+        content: format!("{} + {}", first.mention(), second.mention()),
+        filename: format!(
+            "{}_{}.{}",
+            first.name,
+            second.name,
+            if animated { "gif" } else { "png" }
+        ),
+        data,
+        hidden: false,
+    }
+}
@@ -0,0 +1,388 @@
+pub mod dupes;
+pub mod kitchen;
+pub mod names;
+pub mod pack;
+pub mod proposals;
+pub mod stats;
+pub mod storage;
+pub mod sync;
+
+use serenity::{
+    builder::CreateApplicationCommand,
+    model::prelude::{
+        command::CommandOptionType,
+        interaction::application_command::{
+            ApplicationCommandInteraction, CommandDataOption, CommandDataOptionValue,
+        },
+    },
+    prelude::Context,
+};
+
+use crate::commands::handler::Response;
+
+/// The string value of the option with the given name
+pub fn string_option<'a>(options: &'a [CommandDataOption], name: &str) -> Option<&'a str> {
+    options
+        .iter()
+        .find(|option| option.name == name)
+        .and_then(|option| match option.resolved.as_ref() {
+            Some(CommandDataOptionValue::String(value)) => Some(value.as_str()),
+            _ => None,
+        })
+}
+
+/// Downloads the image of an emote from Discord's CDN
+pub async fn download_emote(id: &str, animated: bool) -> Result<Vec<u8>, String> {
+    let extension = if animated { "gif" } else { "png" };
+    let url = format!("https://cdn.discordapp.com/emojis/{}.{}", id, extension);
+    let response = reqwest::get(&url)
+        .await
+        .and_then(|response| response.error_for_status())
+        .map_err(|why| format!("Unable to download emote: {}", why))?;
+    let bytes = response
+        .bytes()
+        .await
+        .map_err(|why| format!("Unable to download emote: {}", why))?;
+    Ok(bytes.to_vec())
+}
+
+pub async fn run(
+    options: &[CommandDataOption],
+    ctx: &Context,
+    command: &ApplicationCommandInteraction,
+) -> Response {
+    let subcommand = match options.first() {
+        Some(subcommand) => subcommand,
+        None => return Response::Hidden("Please provide a subcommand".to_string()),
+    };
+    match subcommand.name.as_str() {
+        "browse" => names::browse(&subcommand.options).await,
+        "rename" => names::rename(&subcommand.options, ctx, command).await,
+        "alias" | "tag" => {
+            let group = subcommand.name.as_str();
+            let subcommand = match subcommand.options.first() {
+                Some(subcommand) => subcommand,
+                None => return Response::Hidden("Please provide a subcommand".to_string()),
+            };
+            match (group, subcommand.name.as_str()) {
+                ("alias", "add") => names::add_alias(&subcommand.options, command).await,
+                ("alias", "remove") => names::remove_alias(&subcommand.options, command).await,
+                ("tag", "add") => names::add_tag(&subcommand.options, command).await,
+                ("tag", "remove") => names::remove_tag(&subcommand.options, command).await,
+                _ => Response::Hidden("Unknown subcommand".to_string()),
+            }
+        }
+        "proposals" => proposals::configure(&subcommand.options, command).await,
+        "stats" => stats::run(&subcommand.options).await,
+        "sync" => sync::run(&subcommand.options, ctx, command).await,
+        "dupes" => dupes::run().await,
+        "kitchen" => kitchen::run(&subcommand.options, ctx, command).await,
+        "export" => pack::export(command).await,
+        "import" => pack::import(&subcommand.options, ctx, command).await,
+        "storage" => {
+            let subcommand = match subcommand.options.first() {
+                Some(subcommand) => subcommand,
+                None => return Response::Hidden("Please provide a subcommand".to_string()),
+            };
+            match subcommand.name.as_str() {
+                "list" => storage::list(ctx).await,
+                "add" => storage::add(&subcommand.options, ctx, command).await,
+                "remove" => storage::remove(&subcommand.options, command).await,
+                _ => Response::Hidden("Unknown subcommand".to_string()),
+            }
+        }
+        _ => Response::Hidden("Unknown subcommand".to_string()),
+    }
+}
+
+pub fn register(command: &mut CreateApplicationCommand) -> &mut CreateApplicationCommand {
+    command
+        .name("emotes")
+        .description("Manage the emote registry")
+        .create_option(|option| {
+            option
+                .name("browse")
+                .description("List the emotes with a tag, or all tags")
+                .kind(CommandOptionType::SubCommand)
+                .create_sub_option(|option| {
+                    option
+                        .name("tag")
+                        .description("Tag to list the emotes of")
+                        .kind(CommandOptionType::String)
+                        .required(false)
+                })
+        })
+        .create_option(|option| {
+            option
+                .name("rename")
+                .description("Rename an emote on the server and in the registry")
+                .kind(CommandOptionType::SubCommand)
+                .create_sub_option(|option| {
+                    option
+                        .name("emote")
+                        .description("Emote to rename")
+                        .kind(CommandOptionType::String)
+                        .required(true)
+                        .set_autocomplete(true)
+                })
+                .create_sub_option(|option| {
+                    option
+                        .name("name")
+                        .description("New name of the emote")
+                        .kind(CommandOptionType::String)
+                        .required(true)
+                })
+        })
+        .create_option(|option| {
+            option
+                .name("alias")
+                .description("Other names an emote can be found by")
+                .kind(CommandOptionType::SubCommandGroup)
+                .create_sub_option(|option| {
+                    option
+                        .name("add")
+                        .description("Add an alias to an emote")
+                        .kind(CommandOptionType::SubCommand)
+                        .create_sub_option(|option| {
+                            option
+                                .name("emote")
+                                .description("Emote to add the alias to")
+                                .kind(CommandOptionType::String)
+                                .required(true)
+                                .set_autocomplete(true)
+                        })
+                        .create_sub_option(|option| {
+                            option
+                                .name("alias")
+                                .description("The other name")
+                                .kind(CommandOptionType::String)
+                                .required(true)
+                        })
+                })
+                .create_sub_option(|option| {
+                    option
+                        .name("remove")
+                        .description("Remove an alias from an emote")
+                        .kind(CommandOptionType::SubCommand)
+                        .create_sub_option(|option| {
+                            option
+                                .name("emote")
+                                .description("Emote to remove the alias from")
+                                .kind(CommandOptionType::String)
+                                .required(true)
+                                .set_autocomplete(true)
+                        })
+                        .create_sub_option(|option| {
+                            option
+                                .name("alias")
+                                .description("The alias to remove")
+                                .kind(CommandOptionType::String)
+                                .required(true)
+                        })
+                })
+        })
+        .create_option(|option| {
+            option
+                .name("tag")
+                .description("Categories to browse emotes by")
+                .kind(CommandOptionType::SubCommandGroup)
+                .create_sub_option(|option| {
+                    option
+                        .name("add")
+                        .description("Tag an emote")
+                        .kind(CommandOptionType::SubCommand)
+                        .create_sub_option(|option| {
+                            option
+                                .name("emote")
+                                .description("Emote to tag")
+                                .kind(CommandOptionType::String)
+                                .required(true)
+                                .set_autocomplete(true)
+                        })
+                        .create_sub_option(|option| {
+                            option
+                                .name("tag")
+                                .description("The tag")
+                                .kind(CommandOptionType::String)
+                                .required(true)
+                        })
+                })
+                .create_sub_option(|option| {
+                    option
+                        .name("remove")
+                        .description("Remove a tag from an emote")
+                        .kind(CommandOptionType::SubCommand)
+                        .create_sub_option(|option| {
+                            option
+                                .name("emote")
+                                .description("Emote to untag")
+                                .kind(CommandOptionType::String)
+                                .required(true)
+                                .set_autocomplete(true)
+                        })
+                        .create_sub_option(|option| {
+                            option
+                                .name("tag")
+                                .description("The tag to remove")
+                                .kind(CommandOptionType::String)
+                                .required(true)
+                        })
+                })
+        })
+        .create_option(|option| {
+            option
+                .name("proposals")
+                .description("Where emotes proposed by non-admins are reviewed")
+                .kind(CommandOptionType::SubCommand)
+                .create_sub_option(|option| {
+                    option
+                        .name("channel")
+                        .description("Channel to review proposals in")
+                        .kind(CommandOptionType::Channel)
+                        .required(false)
+                })
+                .create_sub_option(|option| {
+                    option
+                        .name("threshold")
+                        .description("Upvotes minus downvotes that approve a proposal")
+                        .kind(CommandOptionType::Integer)
+                        .min_int_value(1)
+                        .required(false)
+                })
+                .create_sub_option(|option| {
+                    option
+                        .name("close")
+                        .description("Stop taking proposals")
+                        .kind(CommandOptionType::Boolean)
+                        .required(false)
+                })
+        })
+        .create_option(|option| {
+            option
+                .name("stats")
+                .description("Most and least used emotes, or the favourites of a user")
+                .kind(CommandOptionType::SubCommand)
+                .create_sub_option(|option| {
+                    option
+                        .name("user")
+                        .description("User to show the favourite emotes of")
+                        .kind(CommandOptionType::User)
+                        .required(false)
+                })
+        })
+        .create_option(|option| {
+            option
+                .name("sync")
+                .description("Update the registry with emotes added, removed or renamed on Discord")
+                .kind(CommandOptionType::SubCommand)
+                .create_sub_option(|option| {
+                    option
+                        .name("preview")
+                        .description("Only show the differences")
+                        .kind(CommandOptionType::Boolean)
+                        .required(false)
+                })
+        })
+        .create_option(|option| {
+            option
+                .name("dupes")
+                .description("List emotes that look alike")
+                .kind(CommandOptionType::SubCommand)
+        })
+        .create_option(|option| {
+            option
+                .name("kitchen")
+                .description("Combine two emotes into one")
+                .kind(CommandOptionType::SubCommand)
+                .create_sub_option(|option| {
+                    option
+                        .name("first")
+                        .description("First emote")
+                        .kind(CommandOptionType::String)
+                        .required(true)
+                        .set_autocomplete(true)
+                })
+                .create_sub_option(|option| {
+                    option
+                        .name("second")
+                        .description("Second emote")
+                        .kind(CommandOptionType::String)
+                        .required(true)
+                        .set_autocomplete(true)
+                })
+                .create_sub_option(|option| {
+                    option
+                        .name("mode")
+                        .description("How to combine them, defaults to side by side")
+                        .kind(CommandOptionType::String)
+                        .add_string_choice("Side by side", "side")
+                        .add_string_choice("Overlay", "overlay")
+                        .add_string_choice("Blend", "blend")
+                        .required(false)
+                })
+                .create_sub_option(|option| {
+                    option
+                        .name("name")
+                        .description("Add the result as an emote with this name")
+                        .kind(CommandOptionType::String)
+                        .required(false)
+                })
+        })
+        .create_option(|option| {
+            option
+                .name("export")
+                .description("Download every emote as a zip")
+                .kind(CommandOptionType::SubCommand)
+        })
+        .create_option(|option| {
+            option
+                .name("import")
+                .description("Create the emotes of an exported zip, skipping duplicates")
+                .kind(CommandOptionType::SubCommand)
+                .create_sub_option(|option| {
+                    option
+                        .name("pack")
+                        .description("Zip made by /emotes export")
+                        .kind(CommandOptionType::Attachment)
+                        .required(true)
+                })
+        })
+        .create_option(|option| {
+            option
+                .name("storage")
+                .description("Guilds the emotes are stored in")
+                .kind(CommandOptionType::SubCommandGroup)
+                .create_sub_option(|option| {
+                    option
+                        .name("list")
+                        .description("List the storage guilds and their free slots")
+                        .kind(CommandOptionType::SubCommand)
+                })
+                .create_sub_option(|option| {
+                    option
+                        .name("add")
+                        .description("Add a guild to store emotes in")
+                        .kind(CommandOptionType::SubCommand)
+                        .create_sub_option(|option| {
+                            option
+                                .name("guild")
+                                .description("ID of the guild, the bot has to be in it")
+                                .kind(CommandOptionType::String)
+                                .required(true)
+                        })
+                })
+                .create_sub_option(|option| {
+                    option
+                        .name("remove")
+                        .description("Stop storing new emotes in a guild")
+                        .kind(CommandOptionType::SubCommand)
+                        .create_sub_option(|option| {
+                            option
+                                .name("guild")
+                                .description("ID of the guild")
+                                .kind(CommandOptionType::String)
+                                .required(true)
+                        })
+                })
+        })
+}
@@ -0,0 +1,229 @@
+use std::collections::BTreeMap;
+
+use serenity::model::prelude::interaction::application_command::{
+    ApplicationCommandInteraction, CommandDataOption,
+};
+use serenity::model::prelude::EmojiId;
+use serenity::prelude::Context;
+
+use crate::commands::create_reaction::valid_name;
+use crate::commands::emotes::string_option;
+use crate::commands::handler::{fit_message, is_admin, Response};
+use crate::commands::react::{find_emote, Emote, EMOTES};
+
+async fn option_emote(options: &[CommandDataOption]) -> Result<Emote, Response> {
+    let query = match string_option(options, "emote") {
+        Some(query) => query,
+        None => return Err(Response::Hidden("Please provide an emote".to_string())),
+    };
+    match find_emote(&EMOTES.read().await, query) {
+        Some(emote) => Ok(emote.clone()),
+        None => Err(Response::Hidden("Unable to find emote".to_string())),
+    }
+}
+
+/// Whether another emote already uses the name as its name or alias
+async fn name_taken(name: &str, id: &str) -> bool {
+    EMOTES
+        .read()
+        .await
+        .iter()
+        .any(|emote| emote.emote != id && emote.answers_to(name))
+}
+
+pub async fn rename(
+    options: &[CommandDataOption],
+    ctx: &Context,
+    command: &ApplicationCommandInteraction,
+) -> Response {
+    if !is_admin(command.user.id.0) {
+        return Response::Hidden("You are not an admin".to_string());
+    }
+    let emote = match option_emote(options).await {
+        Ok(emote) => emote,
+        Err(response) => return response,
+    };
+    let name = match string_option(options, "name") {
+        Some(name) if valid_name(name) => name,
+        _ => {
+            return Response::Hidden(
+                "Emote names must be 2-32 letters, numbers or underscores".to_string(),
+            )
+        }
+    };
+    if name_taken(name, &emote.emote).await {
+        return Response::Hidden("An emote by that name already exists".to_string());
+    }
+    let emoji_id = match emote.emote.parse::<u64>() {
+        Ok(emoji_id) => EmojiId(emoji_id),
+        Err(_) => return Response::Hidden("Invalid emote id".to_string()),
+    };
+    if let Err(why) = emote.guild_id().edit_emoji(&ctx.http, emoji_id, name).await {
+        println!("Unable to rename emote: {}", why);
+        return Response::Hidden("Unable to rename the emote on the server".to_string());
+    }
+    EMOTES
+        .update(|emotes| {
+            if let Some(e) = emotes.iter_mut().find(|e| e.emote == emote.emote) {
+                e.name = name.to_string();
+                e.aliases.retain(|alias| alias != name);
+            }
+        })
+        .await;
+    Response::Shown(format!("Renamed '{}' to '{}'", emote.name, name))
+}
+
+pub async fn add_alias(
+    options: &[CommandDataOption],
+    command: &ApplicationCommandInteraction,
+) -> Response {
+    if !is_admin(command.user.id.0) {
+        return Response::Hidden("You are not an admin".to_string());
+    }
+    let emote = match option_emote(options).await {
+        Ok(emote) => emote,
+        Err(response) => return response,
+    };
+    let alias = match string_option(options, "alias") {
+        Some(alias) if valid_name(alias) => alias,
+        _ => {
+            return Response::Hidden(
+                "Aliases must be 2-32 letters, numbers or underscores".to_string(),
+            )
+        }
+    };
+    if emote.answers_to(alias) {
+        return Response::Hidden(format!("'{}' already answers to '{}'", emote.name, alias));
+    }
+    if name_taken(alias, &emote.emote).await {
+        return Response::Hidden("An emote by that name already exists".to_string());
+    }
+    EMOTES
+        .update(|emotes| {
+            if let Some(e) = emotes.iter_mut().find(|e| e.emote == emote.emote) {
+                e.aliases.push(alias.to_string());
+            }
+        })
+        .await;
+    Response::Shown(format!(
+        "{} can now be used as '{}'",
+        emote.mention(),
+        alias
+    ))
+}
+
+pub async fn remove_alias(
+    options: &[CommandDataOption],
+    command: &ApplicationCommandInteraction,
+) -> Response {
+    if !is_admin(command.user.id.0) {
+        return Response::Hidden("You are not an admin".to_string());
+    }
+    let emote = match option_emote(options).await {
+        Ok(emote) => emote,
+        Err(response) => return response,
+    };
+    let alias = string_option(options, "alias").unwrap_or_default();
+    if !emote.aliases.iter().any(|a| a == alias) {
+        return Response::Hidden(format!("'{}' has no alias '{}'", emote.name, alias));
+    }
+    EMOTES
+        .update(|emotes| {
+            if let Some(e) = emotes.iter_mut().find(|e| e.emote == emote.emote) {
+                e.aliases.retain(|a| a != alias);
+            }
+        })
+        .await;
+    Response::Shown(format!("Removed alias '{}' from '{}'", alias, emote.name))
+}
+
+pub async fn add_tag(
+    options: &[CommandDataOption],
+    command: &ApplicationCommandInteraction,
+) -> Response {
+    if !is_admin(command.user.id.0) {
+        return Response::Hidden("You are not an admin".to_string());
+    }
+    let emote = match option_emote(options).await {
+        Ok(emote) => emote,
+        Err(response) => return response,
+    };
+    // Tags are compared case insensitively
+    let tag = match string_option(options, "tag") {
+        Some(tag) if !tag.trim().is_empty() => tag.trim().to_lowercase(),
+        _ => return Response::Hidden("Please provide a tag".to_string()),
+    };
+    if emote.tags.contains(&tag) {
+        return Response::Hidden(format!("'{}' is already tagged '{}'", emote.name, tag));
+    }
+    EMOTES
+        .update(|emotes| {
+            if let Some(e) = emotes.iter_mut().find(|e| e.emote == emote.emote) {
+                e.tags.push(tag.clone());
+            }
+        })
+        .await;
+    Response::Shown(format!("Tagged {} as '{}'", emote.mention(), tag))
+}
+
+pub async fn remove_tag(
+    options: &[CommandDataOption],
+    command: &ApplicationCommandInteraction,
+) -> Response {
+    if !is_admin(command.user.id.0) {
+        return Response::Hidden("You are not an admin".to_string());
+    }
+    let emote = match option_emote(options).await {
+        Ok(emote) => emote,
+        Err(response) => return response,
+    };
+    let tag = string_option(options, "tag")
+        .unwrap_or_default()
+        .trim()
+        .to_lowercase();
+    if !emote.tags.contains(&tag) {
+        return Response::Hidden(format!("'{}' is not tagged '{}'", emote.name, tag));
+    }
+    EMOTES
+        .update(|emotes| {
+            if let Some(e) = emotes.iter_mut().find(|e| e.emote == emote.emote) {
+                e.tags.retain(|t| *t != tag);
+            }
+        })
+        .await;
+    Response::Shown(format!("Removed tag '{}' from '{}'", tag, emote.name))
+}
+
+/// Lists the emotes with a tag, or every tag when none is given
+pub async fn browse(options: &[CommandDataOption]) -> Response {
+    let emotes = EMOTES.read().await;
+    let content = match string_option(options, "tag") {
+        Some(tag) => {
+            let tag = tag.trim().to_lowercase();
+            let tagged: Vec<String> = emotes
+                .iter()
+                .filter(|emote| emote.tags.contains(&tag))
+                .map(|emote| format!("{} {}", emote.mention(), emote.name))
+                .collect();
+            if tagged.is_empty() {
+                return Response::Hidden(format!("No emotes are tagged '{}'", tag));
+            }
+            format!("Emotes tagged '{}':\n{}", tag, tagged.join("\n"))
+        }
+        None => {
+            let mut tags: BTreeMap<&str, usize> = BTreeMap::new();
+            for tag in emotes.iter().flat_map(|emote| &emote.tags) {
+                *tags.entry(tag).or_default() += 1;
+            }
+            if tags.is_empty() {
+                return Response::Hidden("No emotes are tagged yet".to_string());
+            }
+            let lines: Vec<String> = tags
+                .iter()
+                .map(|(tag, count)| format!("{} ({})", tag, count))
+                .collect();
+            format!("Tags:\n{}", lines.join("\n"))
+        }
+    };
+    Response::Hidden(fit_message(content))
+}
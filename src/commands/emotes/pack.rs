@@ -0,0 +1,218 @@
+use std::collections::HashSet;
+use std::io::{Cursor, Read, Write};
+
+use serde::{Deserialize, Serialize};
+use serenity::model::prelude::interaction::application_command::{
+    ApplicationCommandInteraction, CommandDataOption, CommandDataOptionValue,
+};
+use serenity::prelude::Context;
+use zip::write::FileOptions;
+use zip::{CompressionMethod, ZipArchive, ZipWriter};
+
+use crate::commands::create_reaction::upload_emote;
+use crate::commands::emotes::download_emote;
+use crate::commands::emotes::dupes::find_similar;
+use crate::commands::handler::{fit_message, is_admin, Response};
+use crate::commands::react::EMOTES;
+use crate::imaging::emote::prepare_emote;
+use crate::imaging::process;
+
+// Discord rejects bot uploads larger than this
+const MAX_UPLOAD_BYTES: usize = 25 * 1024 * 1024;
+// Limits on what a pack may unpack to, so a small zip can't expand to gigabytes
+const MAX_MANIFEST_BYTES: u64 = 1024 * 1024;
+const MAX_ENTRY_BYTES: u64 = 8 * 1024 * 1024;
+const MAX_PACK_BYTES: u64 = 100 * 1024 * 1024;
+
+#[derive(Serialize, Deserialize, Debug)]
+struct PackEntry {
+    name: String,
+    file: String,
+    animated: bool,
+    #[serde(default)]
+    aliases: Vec<String>,
+    #[serde(default)]
+    tags: Vec<String>,
+}
+
+pub async fn export(command: &ApplicationCommandInteraction) -> Response {
+    if !is_admin(command.user.id.0) {
+        return Response::Hidden("You are not an admin".to_string());
+    }
+    let emotes = EMOTES.read().await.clone();
+    if emotes.is_empty() {
+        return Response::Hidden("There are no emotes to export".to_string());
+    }
+    let mut manifest = Vec::new();
+    let mut zip = ZipWriter::new(Cursor::new(Vec::new()));
+    // Images are already compressed
+    let stored = FileOptions::default().compression_method(CompressionMethod::Stored);
+    let mut failed = Vec::new();
+    let mut files = HashSet::new();
+    for emote in emotes {
+        let bytes = match download_emote(&emote.emote, emote.animated).await {
+            Ok(bytes) => bytes,
+            Err(_) => {
+                failed.push(emote.name);
+                continue;
+            }
+        };
+        // Emotes in different storage guilds can share a name, but files in a zip can't
+        let extension = if emote.animated { "gif" } else { "png" };
+        let mut file = format!("emotes/{}.{}", emote.name, extension);
+        let mut copy = 1;
+        while !files.insert(file.clone()) {
+            copy += 1;
+            file = format!("emotes/{}_{}.{}", emote.name, copy, extension);
+        }
+        if zip.start_file(&file, stored).is_err() || zip.write_all(&bytes).is_err() {
+            return Response::Hidden("Unable to create the zip file".to_string());
+        }
+        manifest.push(PackEntry {
+            name: emote.name,
+            file,
+            animated: emote.animated,
+            aliases: emote.aliases,
+            tags: emote.tags,
+        });
+    }
+    let contents = serde_json::to_string_pretty(&manifest).unwrap();
+    if zip
+        .start_file("manifest.json", FileOptions::default())
+        .is_err()
+        || zip.write_all(contents.as_bytes()).is_err()
+    {
+        return Response::Hidden("Unable to create the zip file".to_string());
+    }
+    let data = match zip.finish() {
+        Ok(cursor) => cursor.into_inner(),
+        Err(_) => return Response::Hidden("Unable to create the zip file".to_string()),
+    };
+    if data.len() > MAX_UPLOAD_BYTES {
+        return Response::Hidden("The emote pack is too large to upload".to_string());
+    }
+    let mut content = format!("Exported {} emotes", manifest.len());
+    if !failed.is_empty() {
+        content.push_str(&format!(", unable to download {}", failed.join(", ")));
+    }
+    Response::File {
+        content,
+        filename: "emotes.zip".to_string(),
+        data,
+        hidden: true,
+    }
+}
+
+fn read_pack(data: Vec<u8>) -> Result<Vec<(PackEntry, Vec<u8>)>, String> {
+    let mut zip =
+        ZipArchive::new(Cursor::new(data)).map_err(|_| "Please provide a valid zip".to_string())?;
+    let manifest: Vec<PackEntry> = match zip.by_name("manifest.json") {
+        Ok(file) => serde_json::from_reader(file.take(MAX_MANIFEST_BYTES))
+            .map_err(|_| "The manifest of the emote pack is invalid".to_string())?,
+        Err(_) => return Err("The emote pack has no manifest".to_string()),
+    };
+    let mut entries = Vec::new();
+    let mut total = 0;
+    for entry in manifest {
+        let file = match zip.by_name(&entry.file) {
+            Ok(file) => file,
+            Err(_) => continue,
+        };
+        if file.size() > MAX_ENTRY_BYTES {
+            continue;
+        }
+        // The sizes in the zip can lie, so the reads are capped as well
+        let mut bytes = Vec::new();
+        if file
+            .take(MAX_ENTRY_BYTES + 1)
+            .read_to_end(&mut bytes)
+            .is_err()
+            || bytes.len() as u64 > MAX_ENTRY_BYTES
+        {
+            continue;
+        }
+        total += bytes.len() as u64;
+        if total > MAX_PACK_BYTES {
+            return Err("The emote pack is too large".to_string());
+        }
+        entries.push((entry, bytes));
+    }
+    Ok(entries)
+}
+
+pub async fn import(
+    options: &[CommandDataOption],
+    ctx: &Context,
+    command: &ApplicationCommandInteraction,
+) -> Response {
+    if !is_admin(command.user.id.0) {
+        return Response::Hidden("You are not an admin".to_string());
+    }
+    let attachment = match options.first().and_then(|option| option.resolved.as_ref()) {
+        Some(CommandDataOptionValue::Attachment(attachment)) => attachment,
+        _ => return Response::Hidden("Please provide an emote pack".to_string()),
+    };
+    let data = match attachment.download().await {
+        Ok(data) => data,
+        Err(_) => return Response::Hidden("Unable to download the emote pack".to_string()),
+    };
+    let entries = match read_pack(data) {
+        Ok(entries) => entries,
+        Err(why) => return Response::Hidden(why),
+    };
+
+    let emotes = EMOTES.read().await.clone();
+    let mut created = Vec::new();
+    let mut skipped = Vec::new();
+    let mut failed = Vec::new();
+    for (entry, bytes) in entries {
+        if emotes.iter().any(|emote| emote.answers_to(&entry.name)) {
+            skipped.push(entry.name);
+            continue;
+        }
+        let image = match process(move || prepare_emote(&bytes)).await {
+            Ok(image) => image,
+            Err(why) => {
+                failed.push(format!("{} ({})", entry.name, why));
+                continue;
+            }
+        };
+        // Emotes that look like ones we already have, or ones imported earlier in the pack
+        if find_similar(image.hash).await.is_some() {
+            skipped.push(entry.name);
+            continue;
+        }
+        match upload_emote(ctx, &entry.name, image).await {
+            Ok(emote) => {
+                // Keep the aliases that aren't taken by other emotes
+                EMOTES
+                    .update(|emotes| {
+                        let aliases: Vec<String> = entry
+                            .aliases
+                            .into_iter()
+                            .filter(|alias| !emotes.iter().any(|e| e.answers_to(alias)))
+                            .collect();
+                        if let Some(e) = emotes.iter_mut().find(|e| e.emote == emote.emote) {
+                            e.aliases = aliases;
+                            e.tags = entry.tags;
+                        }
+                    })
+                    .await;
+                created.push(emote.name);
+            }
+            Err(why) => failed.push(format!("{} ({})", entry.name, why)),
+        }
+    }
+    let mut content = format!("Imported {} emotes", created.len());
+    if !skipped.is_empty() {
+        content.push_str(&format!("\nSkipped duplicates: {}", skipped.join(", ")));
+    }
+    if !failed.is_empty() {
+        content.push_str(&format!("\nFailed: {}", failed.join(", ")));
+    }
+    if created.is_empty() {
+        Response::Hidden(fit_message(content))
+    } else {
+        Response::Shown(fit_message(content))
+    }
+}
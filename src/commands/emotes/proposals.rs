@@ -0,0 +1,345 @@
+use lazy_static::lazy_static;
+use serde::{Deserialize, Serialize};
+use serenity::builder::CreateComponents;
+use serenity::model::prelude::component::ButtonStyle;
+use serenity::model::prelude::interaction::application_command::{
+    ApplicationCommandInteraction, CommandDataOption, CommandDataOptionValue,
+};
+use serenity::model::prelude::interaction::message_component::MessageComponentInteraction;
+use serenity::model::prelude::{AttachmentType, ChannelId};
+use serenity::prelude::Context;
+
+use crate::commands::create_reaction::{create_emote, valid_name};
+use crate::commands::emotes::dupes::find_similar;
+use crate::commands::handler::{is_admin, Response};
+use crate::commands::react::EMOTES;
+use crate::imaging::emote::prepare_emote;
+use crate::imaging::{download_image, process};
+use crate::store::Store;
+
+const DEFAULT_THRESHOLD: u64 = 5;
+
+lazy_static! {
+    pub static ref PROPOSALS: Store<Proposals> = Store::load("data/emote_proposals.json");
+}
+
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct Proposals {
+    /// Channel proposals are posted in for review
+    pub channel: Option<u64>,
+    /// Upvotes minus downvotes needed to approve a proposal without an admin
+    #[serde(default)]
+    pub threshold: Option<u64>,
+    pub pending: Vec<Proposal>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Proposal {
+    /// Id of the review message, which holds the image
+    pub message: u64,
+    pub name: String,
+    pub user: u64,
+    pub upvotes: Vec<u64>,
+    pub downvotes: Vec<u64>,
+}
+
+fn proposal_buttons<'a>(
+    components: &'a mut CreateComponents,
+    proposal: &Proposal,
+) -> &'a mut CreateComponents {
+    components.create_action_row(|row| {
+        row.create_button(|button| {
+            button
+                .label(format!("👍 {}", proposal.upvotes.len()))
+                .style(ButtonStyle::Secondary)
+                .custom_id("proposal:up")
+        })
+        .create_button(|button| {
+            button
+                .label(format!("👎 {}", proposal.downvotes.len()))
+                .style(ButtonStyle::Secondary)
+                .custom_id("proposal:down")
+        })
+        .create_button(|button| {
+            button
+                .label("Approve")
+                .style(ButtonStyle::Success)
+                .custom_id("proposal:approve")
+        })
+        .create_button(|button| {
+            button
+                .label("Reject")
+                .style(ButtonStyle::Danger)
+                .custom_id("proposal:reject")
+        })
+    })
+}
+
+/// Posts an emote proposal of a non-admin in the review channel
+pub async fn submit(
+    name: &str,
+    bytes: Vec<u8>,
+    ctx: &Context,
+    command: &ApplicationCommandInteraction,
+) -> Response {
+    let channel = match PROPOSALS.read().await.channel {
+        Some(channel) => ChannelId(channel),
+        None => return Response::Hidden("Emote proposals are not open".to_string()),
+    };
+    if !valid_name(name) {
+        return Response::Hidden(
+            "Emote names must be 2-32 letters, numbers or underscores".to_string(),
+        );
+    }
+    let taken = EMOTES
+        .read()
+        .await
+        .iter()
+        .any(|emote| emote.answers_to(name))
+        || PROPOSALS
+            .read()
+            .await
+            .pending
+            .iter()
+            .any(|proposal| proposal.name == name);
+    if taken {
+        return Response::Hidden("An emote by that name already exists".to_string());
+    }
+    // Reviewers see the emote the way it will be uploaded
+    let image = match process(move || prepare_emote(&bytes)).await {
+        Ok(image) => image,
+        Err(why) => return Response::Hidden(why),
+    };
+    if let Some(similar) = find_similar(image.hash).await {
+        return Response::Hidden(format!(
+            "This looks like the existing emote {} '{}'",
+            similar.mention(),
+            similar.name
+        ));
+    }
+    let filename = format!("{}.{}", name, if image.animated { "gif" } else { "png" });
+    let mut proposal = Proposal {
+        message: 0,
+        name: name.to_string(),
+        user: command.user.id.0,
+        upvotes: Vec::new(),
+        downvotes: Vec::new(),
+    };
+    let message = channel
+        .send_message(&ctx.http, |m| {
+            m.content(format!(
+                "<@{}> proposed the emote '{}'",
+                proposal.user, name
+            ))
+            .add_file(AttachmentType::Bytes {
+                data: image.data.into(),
+                filename,
+            })
+            .components(|components| proposal_buttons(components, &proposal))
+        })
+        .await;
+    let message = match message {
+        Ok(message) => message,
+        Err(why) => {
+            println!("Unable to post emote proposal: {}", why);
+            return Response::Hidden("Unable to post the proposal".to_string());
+        }
+    };
+    proposal.message = message.id.0;
+    PROPOSALS
+        .update(|proposals| proposals.pending.push(proposal))
+        .await;
+    Response::Hidden(format!(
+        "Proposed '{}', it will be added once approved",
+        name
+    ))
+}
+
+/// Takes the proposal out of the pending list, so only one approval or rejection can act on it
+async fn claim(message: u64) -> Option<Proposal> {
+    PROPOSALS
+        .update(|proposals| {
+            let index = proposals
+                .pending
+                .iter()
+                .position(|proposal| proposal.message == message)?;
+            Some(proposals.pending.remove(index))
+        })
+        .await
+}
+
+/// Creates the emote from the image of the review message, the proposal must be claimed
+async fn approve(
+    proposal: Proposal,
+    ctx: &Context,
+    component: &MessageComponentInteraction,
+) -> Response {
+    let bytes = match component.message.attachments.first() {
+        Some(attachment) => download_image(&attachment.url).await,
+        None => Err("The proposal has no image".to_string()),
+    };
+    let emote = match bytes {
+        Ok(bytes) => create_emote(ctx, &proposal.name, bytes).await,
+        Err(why) => Err(why),
+    };
+    let emote = match emote {
+        Ok(emote) => emote,
+        Err(why) => {
+            // Put the proposal back so it can be approved once the problem is fixed
+            PROPOSALS
+                .update(|proposals| proposals.pending.push(proposal))
+                .await;
+            return Response::Hidden(why);
+        }
+    };
+    close(
+        &format!("Added {} '{}'", emote.mention(), emote.name),
+        ctx,
+        component,
+    )
+    .await;
+    Response::Shown(format!(
+        "Added {} '{}' proposed by <@{}>",
+        emote.mention(),
+        emote.name,
+        proposal.user
+    ))
+}
+
+/// Removes the buttons of the review message
+async fn close(outcome: &str, ctx: &Context, component: &MessageComponentInteraction) {
+    let content = format!("{}\n{}", component.message.content, outcome);
+    if let Err(why) = component
+        .channel_id
+        .edit_message(&ctx.http, component.message.id, |m| {
+            m.content(content).components(|components| components)
+        })
+        .await
+    {
+        println!("Unable to close emote proposal: {}", why);
+    }
+}
+
+/// Handles the vote, approve and reject buttons of a review message
+pub async fn review(
+    data: &str,
+    ctx: &Context,
+    component: &MessageComponentInteraction,
+) -> Response {
+    const CLOSED: &str = "This proposal was already closed";
+    let message = component.message.id.0;
+    let user = component.user.id.0;
+    match data {
+        "approve" | "reject" if !is_admin(user) => {
+            Response::Hidden("You are not an admin".to_string())
+        }
+        "approve" => match claim(message).await {
+            Some(proposal) => approve(proposal, ctx, component).await,
+            None => Response::Hidden(CLOSED.to_string()),
+        },
+        "reject" => match claim(message).await {
+            Some(proposal) => {
+                close("Rejected", ctx, component).await;
+                Response::Hidden(format!("Rejected '{}'", proposal.name))
+            }
+            None => Response::Hidden(CLOSED.to_string()),
+        },
+        "up" | "down" => {
+            // The vote is counted against the stored proposal, so votes at the same time all count
+            let voted = PROPOSALS
+                .update(|proposals| {
+                    let threshold = proposals.threshold.unwrap_or(DEFAULT_THRESHOLD);
+                    let proposal = proposals
+                        .pending
+                        .iter_mut()
+                        .find(|proposal| proposal.message == message)?;
+                    if user == proposal.user {
+                        return Some(Err("You can't vote on your own proposal".to_string()));
+                    }
+                    // Voting again takes the vote back, voting the other way moves it
+                    let (votes, other) = if data == "up" {
+                        (&mut proposal.upvotes, &mut proposal.downvotes)
+                    } else {
+                        (&mut proposal.downvotes, &mut proposal.upvotes)
+                    };
+                    other.retain(|&voter| voter != user);
+                    if votes.contains(&user) {
+                        votes.retain(|&voter| voter != user);
+                    } else {
+                        votes.push(user);
+                    }
+                    let score = proposal.upvotes.len() as i64 - proposal.downvotes.len() as i64;
+                    Some(Ok((proposal.clone(), score >= threshold as i64)))
+                })
+                .await;
+            let (proposal, approved) = match voted {
+                Some(Ok(voted)) => voted,
+                Some(Err(why)) => return Response::Hidden(why),
+                None => return Response::Hidden(CLOSED.to_string()),
+            };
+            if approved {
+                return match claim(message).await {
+                    Some(proposal) => approve(proposal, ctx, component).await,
+                    None => Response::Hidden(CLOSED.to_string()),
+                };
+            }
+            if let Err(why) = component
+                .channel_id
+                .edit_message(&ctx.http, component.message.id, |m| {
+                    m.components(|components| proposal_buttons(components, &proposal))
+                })
+                .await
+            {
+                println!("Unable to update votes: {}", why);
+            }
+            Response::Hidden(format!("Your vote on '{}' was counted", proposal.name))
+        }
+        _ => Response::Hidden("Unknown component".to_string()),
+    }
+}
+
+/// Opens proposals in a review channel, or changes the vote threshold
+pub async fn configure(
+    options: &[CommandDataOption],
+    command: &ApplicationCommandInteraction,
+) -> Response {
+    if !is_admin(command.user.id.0) {
+        return Response::Hidden("You are not an admin".to_string());
+    }
+    let mut channel = None;
+    let mut threshold = None;
+    let mut close = false;
+    for option in options {
+        match (option.name.as_str(), option.resolved.as_ref()) {
+            ("channel", Some(CommandDataOptionValue::Channel(value))) => channel = Some(value.id.0),
+            ("threshold", Some(CommandDataOptionValue::Integer(value))) => {
+                threshold = Some((*value).max(1) as u64)
+            }
+            ("close", Some(CommandDataOptionValue::Boolean(value))) => close = *value,
+            _ => {}
+        }
+    }
+    let (channel, threshold) = PROPOSALS
+        .update(|proposals| {
+            if close {
+                proposals.channel = None;
+            } else if let Some(channel) = channel {
+                proposals.channel = Some(channel);
+            }
+            if threshold.is_some() {
+                proposals.threshold = threshold;
+            }
+            (
+                proposals.channel,
+                proposals.threshold.unwrap_or(DEFAULT_THRESHOLD),
+            )
+        })
+        .await;
+    match channel {
+        Some(channel) => Response::Shown(format!(
+            "Emote proposals are reviewed in <#{}>, {} net votes approve them",
+            channel, threshold
+        )),
+        None => Response::Shown("Emote proposals are closed".to_string()),
+    }
+}
@@ -0,0 +1,116 @@
+use chrono::Utc;
+use serenity::model::prelude::interaction::application_command::{
+    CommandDataOption, CommandDataOptionValue,
+};
+use serenity::model::prelude::EmojiId;
+
+use crate::commands::handler::{fit_message, Response};
+use crate::commands::react::{Emote, EMOTES};
+use crate::commands::usage::{EmoteUsage, USAGE};
+
+// Emotes shown in the top and bottom lists
+const LIST_LENGTH: usize = 10;
+// Emotes unused for this long are candidates for cleanup
+const UNUSED_DAYS: i64 = 90;
+
+fn describe(emote: &Emote, usage: Option<&EmoteUsage>) -> String {
+    match usage {
+        Some(usage) => format!(
+            "{} {}: {} (/react {}, messages {}, reactions {})",
+            emote.mention(),
+            emote.name,
+            usage.count,
+            usage.reacts,
+            usage.messages,
+            usage.reactions
+        ),
+        None => format!("{} {}: 0", emote.mention(), emote.name),
+    }
+}
+
+/// When the emote was last used, emotes that were never used count from when they were created
+fn last_used(emote: &Emote, usage: Option<&EmoteUsage>) -> i64 {
+    match usage {
+        Some(usage) => usage.last_used,
+        None => emote
+            .emote
+            .parse()
+            .map_or(0, |id| EmojiId(id).created_at().unix_timestamp()),
+    }
+}
+
+async fn overview() -> Response {
+    let emotes = EMOTES.read().await;
+    let usage = USAGE.read().await;
+    if emotes.is_empty() {
+        return Response::Hidden("There are no emotes yet".to_string());
+    }
+    let mut ranked: Vec<&Emote> = emotes.iter().collect();
+    let count = |emote: &Emote| usage.get(&emote.emote).map_or(0, |u| u.count);
+    ranked.sort_by(|a, b| count(b).cmp(&count(a)).then(a.name.cmp(&b.name)));
+
+    let top: Vec<String> = ranked
+        .iter()
+        .take(LIST_LENGTH)
+        .map(|emote| describe(emote, usage.get(&emote.emote)))
+        .collect();
+    let bottom: Vec<String> = ranked
+        .iter()
+        .rev()
+        .take(LIST_LENGTH.min(ranked.len().saturating_sub(LIST_LENGTH)))
+        .map(|emote| describe(emote, usage.get(&emote.emote)))
+        .collect();
+    let cutoff = Utc::now().timestamp() - UNUSED_DAYS * 86400;
+    let unused: Vec<&str> = ranked
+        .iter()
+        .filter(|emote| last_used(emote, usage.get(&emote.emote)) < cutoff)
+        .map(|emote| emote.name.as_str())
+        .collect();
+
+    let mut content = format!("Most used:\n{}", top.join("\n"));
+    if !bottom.is_empty() {
+        content.push_str(&format!("\n\nLeast used:\n{}", bottom.join("\n")));
+    }
+    if !unused.is_empty() {
+        content.push_str(&format!(
+            "\n\nUnused for {} days: {}",
+            UNUSED_DAYS,
+            unused.join(", ")
+        ));
+    }
+    Response::Hidden(fit_message(content))
+}
+
+/// The emotes a user uses the most
+async fn favourites(user: u64) -> Response {
+    let emotes = EMOTES.read().await;
+    let usage = USAGE.read().await;
+    let mut favourites: Vec<(&Emote, u64)> = emotes
+        .iter()
+        .filter_map(|emote| {
+            let count = *usage.get(&emote.emote)?.users.get(&user)?;
+            Some((emote, count))
+        })
+        .collect();
+    if favourites.is_empty() {
+        return Response::Hidden(format!("<@{}> hasn't used any emotes yet", user));
+    }
+    favourites.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.name.cmp(&b.0.name)));
+    let lines: Vec<String> = favourites
+        .iter()
+        .take(LIST_LENGTH)
+        .map(|(emote, count)| format!("{} {}: {}", emote.mention(), emote.name, count))
+        .collect();
+    Response::Hidden(fit_message(format!(
+        "Favourite emotes of <@{}>:\n{}",
+        user,
+        lines.join("\n")
+    )))
+}
+
+pub async fn run(options: &[CommandDataOption]) -> Response {
+    match options.first().and_then(|option| option.resolved.as_ref()) {
+        Some(CommandDataOptionValue::User(user, _)) => favourites(user.id.0).await,
+        _ => overview().await,
+    }
+}
@@ -0,0 +1,152 @@
+use lazy_static::lazy_static;
+use serenity::model::prelude::interaction::application_command::{
+    ApplicationCommandInteraction, CommandDataOption, CommandDataOptionValue,
+};
+use serenity::model::prelude::{Emoji, GuildId, PremiumTier};
+use serenity::prelude::Context;
+
+use crate::commands::create_reaction::get_home_channel;
+use crate::commands::handler::{is_admin, Response};
+use crate::store::Store;
+
+lazy_static! {
+    /// Storage guilds besides the home guild
+    pub static ref STORAGE_GUILDS: Store<Vec<u64>> = Store::load("data/storage_guilds.json");
+}
+
+/// Guilds emotes are stored in, the home guild comes first if one is set
+pub async fn storage_guilds() -> Vec<GuildId> {
+    let mut guilds = vec![get_home_channel().guild_id];
+    for guild in STORAGE_GUILDS.read().await.iter() {
+        if !guilds.contains(guild) {
+            guilds.push(*guild);
+        }
+    }
+    guilds.retain(|&guild| guild != 0);
+    guilds.into_iter().map(GuildId).collect()
+}
+
+/// Emoji slots for each of static and animated emotes
+fn slot_limit(tier: PremiumTier) -> usize {
+    match tier {
+        PremiumTier::Tier1 => 100,
+        PremiumTier::Tier2 => 150,
+        PremiumTier::Tier3 => 250,
+        _ => 50,
+    }
+}
+
+/// Free static and animated slots of a guild
+async fn free_slots(ctx: &Context, guild_id: GuildId) -> Option<(usize, usize)> {
+    let guild = match guild_id.to_partial_guild(&ctx.http).await {
+        Ok(guild) => guild,
+        Err(why) => {
+            println!("Unable to get storage guild {}: {}", guild_id, why);
+            return None;
+        }
+    };
+    let limit = slot_limit(guild.premium_tier);
+    let animated = guild.emojis.values().filter(|emoji| emoji.animated).count();
+    let still = guild.emojis.len() - animated;
+    Some((limit.saturating_sub(still), limit.saturating_sub(animated)))
+}
+
+/// The first storage guild with a free slot for the emote
+pub async fn pick_guild(ctx: &Context, animated: bool) -> Result<GuildId, String> {
+    for guild_id in storage_guilds().await {
+        if let Some((free_static, free_animated)) = free_slots(ctx, guild_id).await {
+            if (animated && free_animated > 0) || (!animated && free_static > 0) {
+                return Ok(guild_id);
+            }
+        }
+    }
+    Err("All storage guilds are full".to_string())
+}
+
+/// Looks for an emoji by name in all storage guilds, for emotes missing from the registry
+pub async fn find_in_storage(ctx: &Context, name: &str) -> Option<Emoji> {
+    for guild_id in storage_guilds().await {
+        if let Ok(emojis) = guild_id.emojis(&ctx.http).await {
+            if let Some(emoji) = emojis.into_iter().find(|emoji| emoji.name == name) {
+                return Some(emoji);
+            }
+        }
+    }
+    None
+}
+
+pub async fn list(ctx: &Context) -> Response {
+    let mut lines = Vec::new();
+    for guild_id in storage_guilds().await {
+        match free_slots(ctx, guild_id).await {
+            Some((still, animated)) => lines.push(format!(
+                "{}: {} static and {} animated slots free",
+                guild_id, still, animated
+            )),
+            None => lines.push(format!("{}: unavailable", guild_id)),
+        }
+    }
+    Response::Hidden(format!("Storage guilds:\n{}", lines.join("\n")))
+}
+
+pub async fn add(
+    options: &[CommandDataOption],
+    ctx: &Context,
+    command: &ApplicationCommandInteraction,
+) -> Response {
+    if !is_admin(command.user.id.0) {
+        return Response::Hidden("You are not an admin".to_string());
+    }
+    let guild_id = match options.first().and_then(|option| option.resolved.as_ref()) {
+        Some(CommandDataOptionValue::String(id)) => match id.parse::<u64>() {
+            Ok(id) => GuildId(id),
+            Err(_) => return Response::Hidden("Invalid guild ID".to_string()),
+        },
+        _ => return Response::Hidden("Please provide a guild ID".to_string()),
+    };
+    if free_slots(ctx, guild_id).await.is_none() {
+        return Response::Hidden("The bot is not in that guild".to_string());
+    }
+    let added = STORAGE_GUILDS
+        .update(|guilds| {
+            if guild_id.0 == get_home_channel().guild_id || guilds.contains(&guild_id.0) {
+                return false;
+            }
+            guilds.push(guild_id.0);
+            true
+        })
+        .await;
+    if !added {
+        return Response::Hidden("That guild is already a storage guild".to_string());
+    }
+    Response::Shown(format!("Added storage guild {}", guild_id))
+}
+
+pub async fn remove(
+    options: &[CommandDataOption],
+    command: &ApplicationCommandInteraction,
+) -> Response {
+    if !is_admin(command.user.id.0) {
+        return Response::Hidden("You are not an admin".to_string());
+    }
+    let id = match options.first().and_then(|option| option.resolved.as_ref()) {
+        Some(CommandDataOptionValue::String(id)) => id.parse::<u64>().unwrap_or_default(),
+        _ => return Response::Hidden("Please provide a guild ID".to_string()),
+    };
+    let removed = STORAGE_GUILDS
+        .update(|guilds| {
+            if !guilds.contains(&id) {
+                return false;
+            }
+            guilds.retain(|&guild| guild != id);
+            true
+        })
+        .await;
+    if !removed {
+        return Response::Hidden("That guild is not a storage guild".to_string());
+    }
+    Response::Shown(format!(
+        "Removed storage guild {}, its emotes still work until deleted",
+        id
+    ))
+}
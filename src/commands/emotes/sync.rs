@@ -0,0 +1,184 @@
+use serenity::model::prelude::interaction::application_command::{
+    ApplicationCommandInteraction, CommandDataOption, CommandDataOptionValue,
+};
+use serenity::model::prelude::{Emoji, GuildId};
+use serenity::prelude::Context;
+
+use crate::commands::emotes::dupes::backfill_hashes;
+use crate::commands::emotes::storage::storage_guilds;
+use crate::commands::handler::{fit_message, is_admin, Response};
+use crate::commands::react::{Emote, EMOTES};
+
+/// Differences between the registry and the emojis actually in the guilds
+#[derive(Default)]
+pub struct SyncDiff {
+    /// Emojis added through Discord that are missing from the registry
+    pub added: Vec<Emote>,
+    /// Registry entries whose emoji no longer exists
+    pub removed: Vec<Emote>,
+    /// Emojis renamed through Discord, as id, old name and new name
+    pub renamed: Vec<(String, String, String)>,
+    /// Entries with the wrong animated flag, as id, name and whether the emoji is animated
+    pub retyped: Vec<(String, String, bool)>,
+}
+
+impl SyncDiff {
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty()
+            && self.removed.is_empty()
+            && self.renamed.is_empty()
+            && self.retyped.is_empty()
+    }
+
+    fn extend(&mut self, other: SyncDiff) {
+        self.added.extend(other.added);
+        self.removed.extend(other.removed);
+        self.renamed.extend(other.renamed);
+        self.retyped.extend(other.retyped);
+    }
+
+    pub fn describe(&self) -> String {
+        if self.is_empty() {
+            return "The registry matches the guild emojis".to_string();
+        }
+        let mut lines = Vec::new();
+        for emote in &self.added {
+            lines.push(format!("+ {} {}", emote.mention(), emote.name));
+        }
+        for emote in &self.removed {
+            lines.push(format!("- {} ({})", emote.name, emote.emote));
+        }
+        for (_, old, new) in &self.renamed {
+            lines.push(format!("~ {} -> {}", old, new));
+        }
+        for (_, name, animated) in &self.retyped {
+            let kind = if *animated { "animated" } else { "static" };
+            lines.push(format!("~ {} is {}", name, kind));
+        }
+        lines.join("\n")
+    }
+}
+
+/// Compares the registry entries of one guild with the emojis it really has
+fn diff_guild(emotes: &[Emote], guild_id: GuildId, emojis: &[Emoji]) -> SyncDiff {
+    let mut diff = SyncDiff::default();
+    for emote in emotes.iter().filter(|emote| emote.guild_id() == guild_id) {
+        let emoji = match emojis
+            .iter()
+            .find(|emoji| emoji.id.to_string() == emote.emote)
+        {
+            Some(emoji) => emoji,
+            None => {
+                diff.removed.push(emote.clone());
+                continue;
+            }
+        };
+        if emoji.name != emote.name {
+            diff.renamed
+                .push((emote.emote.clone(), emote.name.clone(), emoji.name.clone()));
+        }
+        if emoji.animated != emote.animated {
+            diff.retyped
+                .push((emote.emote.clone(), emoji.name.clone(), emoji.animated));
+        }
+    }
+    for emoji in emojis {
+        let id = emoji.id.to_string();
+        if !emotes.iter().any(|emote| emote.emote == id) {
+            diff.added.push(Emote {
+                name: emoji.name.clone(),
+                emote: id,
+                guild: Some(guild_id.0),
+                animated: emoji.animated,
+                ..Default::default()
+            });
+        }
+    }
+    diff
+}
+
+async fn apply(diff: &SyncDiff) {
+    if diff.is_empty() {
+        return;
+    }
+    EMOTES
+        .update(|emotes| {
+            emotes.retain(|emote| !diff.removed.iter().any(|e| e.emote == emote.emote));
+            for (id, _, name) in &diff.renamed {
+                if let Some(emote) = emotes.iter_mut().find(|emote| emote.emote == *id) {
+                    emote.name = name.clone();
+                    emote.aliases.retain(|alias| alias != name);
+                }
+            }
+            for (id, _, animated) in &diff.retyped {
+                if let Some(emote) = emotes.iter_mut().find(|emote| emote.emote == *id) {
+                    emote.animated = *animated;
+                }
+            }
+            emotes.extend(diff.added.iter().cloned());
+        })
+        .await;
+}
+
+/// Storage guilds plus guilds that registry entries still point at,
+/// older entries point at no guild until a home guild is set
+async fn synced_guilds() -> Vec<GuildId> {
+    let mut guilds = storage_guilds().await;
+    for emote in EMOTES.read().await.iter() {
+        let guild_id = emote.guild_id();
+        if guild_id.0 != 0 && !guilds.contains(&guild_id) {
+            guilds.push(guild_id);
+        }
+    }
+    guilds
+}
+
+/// Reconciles the registry with the emojis of every guild it uses.
+/// Guilds that can't be fetched are left alone so a failed request doesn't empty the registry.
+pub async fn reconcile(ctx: &Context, preview: bool) -> SyncDiff {
+    let mut diff = SyncDiff::default();
+    for guild_id in synced_guilds().await {
+        match guild_id.emojis(&ctx.http).await {
+            Ok(emojis) => diff.extend(diff_guild(&EMOTES.read().await, guild_id, &emojis)),
+            Err(why) => println!("Unable to get emojis of guild {}: {}", guild_id, why),
+        }
+    }
+    if !preview {
+        apply(&diff).await;
+        backfill_hashes().await;
+    }
+    diff
+}
+
+/// Reconciles a single guild after Discord reported its emojis changed
+pub async fn reconcile_guild(guild_id: GuildId, emojis: Vec<Emoji>) {
+    if !synced_guilds().await.contains(&guild_id) {
+        return;
+    }
+    let diff = diff_guild(&EMOTES.read().await, guild_id, &emojis);
+    if !diff.is_empty() {
+        println!("Synced emotes of guild {}:\n{}", guild_id, diff.describe());
+    }
+    apply(&diff).await;
+}
+
+pub async fn run(
+    options: &[CommandDataOption],
+    ctx: &Context,
+    command: &ApplicationCommandInteraction,
+) -> Response {
+    if !is_admin(command.user.id.0) {
+        return Response::Hidden("You are not an admin".to_string());
+    }
+    let preview = matches!(
+        options.first().and_then(|option| option.resolved.as_ref()),
+        Some(CommandDataOptionValue::Boolean(true))
+    );
+    let diff = reconcile(ctx, preview).await;
+    let content = if preview || diff.is_empty() {
+        diff.describe()
+    } else {
+        format!("Synced the registry:\n{}", diff.describe())
+    };
+    Response::Hidden(fit_message(content))
+}
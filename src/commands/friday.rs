@@ -0,0 +1,342 @@
+use serenity::{
+    builder::CreateApplicationCommand,
+    model::prelude::{
+        command::CommandOptionType,
+        interaction::application_command::{
+            ApplicationCommandInteraction, CommandDataOption, CommandDataOptionValue,
+        },
+    },
+    prelude::Context,
+};
+
+use crate::commands::handler::{is_admin, Response};
+use crate::friday::config::CONFIG;
+use crate::friday::friday::{
+    friday_active, friday_started, get_angels, get_pending_angels, get_permanent_angels,
+    read_friday_channel, remove_user, set_friday_channel, start_friday,
+};
+
+pub async fn run(
+    options: &[CommandDataOption],
+    ctx: &Context,
+    command: &ApplicationCommandInteraction,
+) -> Response {
+    let subcommand = match options.first() {
+        Some(subcommand) => subcommand,
+        None => return Response::Hidden("Please provide a subcommand".to_string()),
+    };
+    match subcommand.name.as_str() {
+        "status" => status().await,
+        "angels" => angels().await,
+        "unpardon" => unpardon(&subcommand.options, command).await,
+        "setchannel" => setchannel(&subcommand.options, command),
+        "force-start" => force_start(ctx, command).await,
+        "optout" => optout(command, true).await,
+        "optin" => optout(command, false).await,
+        "allowchannel" | "denychannel" | "resetchannel" => {
+            scope_channel(subcommand.name.as_str(), &subcommand.options, command).await
+        }
+        "ratelimit" => ratelimit(&subcommand.options, command).await,
+        _ => Response::Hidden("Unknown subcommand".to_string()),
+    }
+}
+
+async fn status() -> Response {
+    let (guild_id, channel_id) = read_friday_channel();
+    let channel = if channel_id == 0 {
+        "not set".to_string()
+    } else {
+        format!("<#{}> (guild {})", channel_id, guild_id)
+    };
+    let config = CONFIG.read().await;
+    let limit = if config.hourly_limit == 0 {
+        "unlimited".to_string()
+    } else {
+        format!("{} per user per hour", config.hourly_limit)
+    };
+    Response::Hidden(format!(
+        "Friday started: {}\nFriday rules apply: {}\nAngels: {} ({} permanent)\nAnnouncement channel: {}\nAllowed channels: {}\nDenied channels: {}\nOpted out users: {}\nReaction limit: {}",
+        friday_started().await,
+        friday_active().await,
+        get_angels().await.len(),
+        get_permanent_angels().await.len(),
+        channel,
+        channel_list(&config.allowed_channels, "all"),
+        channel_list(&config.denied_channels, "none"),
+        config.opted_out.len(),
+        limit
+    ))
+}
+
+fn channel_list(channels: &[u64], empty: &str) -> String {
+    if channels.is_empty() {
+        return empty.to_string();
+    }
+    channels
+        .iter()
+        .map(|channel| format!("<#{}>", channel))
+        .collect::<Vec<String>>()
+        .join(", ")
+}
+
+async fn angels() -> Response {
+    let angels = get_angels().await;
+    let pending = get_pending_angels().await;
+    let permanent = get_permanent_angels().await;
+    if angels.is_empty() && pending.is_empty() && permanent.is_empty() {
+        return Response::Hidden("There are no angels, everyone is a sinner".to_string());
+    }
+    let list = angels
+        .iter()
+        .map(|angel| format!("<@{}>", angel))
+        .chain(
+            pending
+                .iter()
+                .map(|angel| format!("<@{}> (next Friday)", angel)),
+        )
+        .chain(
+            permanent
+                .iter()
+                .map(|angel| format!("<@{}> (permanent)", angel)),
+        )
+        .collect::<Vec<String>>()
+        .join("\n");
+    Response::Hidden(format!("Angels:\n{}", list))
+}
+
+async fn unpardon(
+    options: &[CommandDataOption],
+    command: &ApplicationCommandInteraction,
+) -> Response {
+    if !is_admin(command.user.id.0) {
+        return Response::Hidden("You are not an admin".to_string());
+    }
+    let user = options.first().and_then(|option| option.resolved.as_ref());
+    if let Some(CommandDataOptionValue::User(u, _)) = user {
+        if remove_user(u.id.0).await {
+            Response::Shown(format!("Removed {} from the angels", u.name))
+        } else {
+            Response::Hidden(format!("{} is not an angel", u.name))
+        }
+    } else {
+        Response::Hidden("Please provide a valid user".to_string())
+    }
+}
+
+fn setchannel(options: &[CommandDataOption], command: &ApplicationCommandInteraction) -> Response {
+    if !is_admin(command.user.id.0) {
+        return Response::Hidden("You are not an admin".to_string());
+    }
+    let guild_id = match command.guild_id {
+        Some(guild_id) => guild_id,
+        None => return Response::Hidden("This command only works in a server".to_string()),
+    };
+    let channel_id = match options.first().and_then(|option| option.resolved.as_ref()) {
+        Some(CommandDataOptionValue::Channel(channel)) => channel.id,
+        _ => command.channel_id,
+    };
+    set_friday_channel(guild_id.0, channel_id.0);
+    Response::Shown(format!("Friday will now be announced in <#{}>", channel_id))
+}
+
+async fn force_start(ctx: &Context, command: &ApplicationCommandInteraction) -> Response {
+    if !is_admin(command.user.id.0) {
+        return Response::Hidden("You are not an admin".to_string());
+    }
+    if read_friday_channel().1 == 0 {
+        return Response::Hidden("Set a channel with /friday setchannel first".to_string());
+    }
+    start_friday(ctx, true).await;
+    Response::Hidden("Friday has been started".to_string())
+}
+
+async fn optout(command: &ApplicationCommandInteraction, opt_out: bool) -> Response {
+    let user = command.user.id.0;
+    CONFIG
+        .update(|config| {
+            config.opted_out.retain(|&x| x != user);
+            if opt_out {
+                config.opted_out.push(user);
+            }
+        })
+        .await;
+    if opt_out {
+        Response::Hidden("You will no longer get Friday reactions".to_string())
+    } else {
+        Response::Hidden("You will get Friday reactions again".to_string())
+    }
+}
+
+async fn scope_channel(
+    mode: &str,
+    options: &[CommandDataOption],
+    command: &ApplicationCommandInteraction,
+) -> Response {
+    if !is_admin(command.user.id.0) {
+        return Response::Hidden("You are not an admin".to_string());
+    }
+    let channel_id = match options.first().and_then(|option| option.resolved.as_ref()) {
+        Some(CommandDataOptionValue::Channel(channel)) => channel.id,
+        _ => command.channel_id,
+    };
+    let message = CONFIG
+        .update(|config| {
+            config.allowed_channels.retain(|&x| x != channel_id.0);
+            config.denied_channels.retain(|&x| x != channel_id.0);
+            match mode {
+                "allowchannel" => {
+                    config.allowed_channels.push(channel_id.0);
+                    format!("Friday reactions are allowed in <#{}>", channel_id)
+                }
+                "denychannel" => {
+                    config.denied_channels.push(channel_id.0);
+                    format!("Friday reactions are denied in <#{}>", channel_id)
+                }
+                _ => format!("Removed <#{}> from the channel lists", channel_id),
+            }
+        })
+        .await;
+    Response::Shown(message)
+}
+
+async fn ratelimit(
+    options: &[CommandDataOption],
+    command: &ApplicationCommandInteraction,
+) -> Response {
+    if !is_admin(command.user.id.0) {
+        return Response::Hidden("You are not an admin".to_string());
+    }
+    if let Some(CommandDataOptionValue::Integer(limit)) =
+        options.first().and_then(|option| option.resolved.as_ref())
+    {
+        CONFIG
+            .update(|config| config.hourly_limit = *limit as u32)
+            .await;
+        if *limit == 0 {
+            Response::Shown("Removed the Friday reaction limit".to_string())
+        } else {
+            Response::Shown(format!(
+                "Friday reactions limited to {} per user per hour",
+                limit
+            ))
+        }
+    } else {
+        Response::Hidden("Please provide a valid limit".to_string())
+    }
+}
+
+pub fn register(command: &mut CreateApplicationCommand) -> &mut CreateApplicationCommand {
+    command
+        .name("friday")
+        .description("Inspect and manage Friday")
+        .create_option(|option| {
+            option
+                .name("status")
+                .description("Show the current Friday state")
+                .kind(CommandOptionType::SubCommand)
+        })
+        .create_option(|option| {
+            option
+                .name("angels")
+                .description("List everyone who has been pardoned this Friday")
+                .kind(CommandOptionType::SubCommand)
+        })
+        .create_option(|option| {
+            option
+                .name("unpardon")
+                .description("Remove a user from the angels")
+                .kind(CommandOptionType::SubCommand)
+                .create_sub_option(|option| {
+                    option
+                        .name("user")
+                        .description("Choose a user to unpardon")
+                        .kind(CommandOptionType::User)
+                        .required(true)
+                })
+        })
+        .create_option(|option| {
+            option
+                .name("setchannel")
+                .description("Set the channel Friday is announced in")
+                .kind(CommandOptionType::SubCommand)
+                .create_sub_option(|option| {
+                    option
+                        .name("channel")
+                        .description("Defaults to the current channel")
+                        .kind(CommandOptionType::Channel)
+                        .required(false)
+                })
+        })
+        .create_option(|option| {
+            option
+                .name("force-start")
+                .description("Start Friday now, resetting the angels")
+                .kind(CommandOptionType::SubCommand)
+        })
+        .create_option(|option| {
+            option
+                .name("optout")
+                .description("Stop getting Friday reactions")
+                .kind(CommandOptionType::SubCommand)
+        })
+        .create_option(|option| {
+            option
+                .name("optin")
+                .description("Get Friday reactions again")
+                .kind(CommandOptionType::SubCommand)
+        })
+        .create_option(|option| {
+            option
+                .name("allowchannel")
+                .description("Only react in allowed channels, once any are set")
+                .kind(CommandOptionType::SubCommand)
+                .create_sub_option(|option| {
+                    option
+                        .name("channel")
+                        .description("Defaults to the current channel")
+                        .kind(CommandOptionType::Channel)
+                        .required(false)
+                })
+        })
+        .create_option(|option| {
+            option
+                .name("denychannel")
+                .description("Never react in a channel")
+                .kind(CommandOptionType::SubCommand)
+                .create_sub_option(|option| {
+                    option
+                        .name("channel")
+                        .description("Defaults to the current channel")
+                        .kind(CommandOptionType::Channel)
+                        .required(false)
+                })
+        })
+        .create_option(|option| {
+            option
+                .name("resetchannel")
+                .description("Remove a channel from the allow and deny lists")
+                .kind(CommandOptionType::SubCommand)
+                .create_sub_option(|option| {
+                    option
+                        .name("channel")
+                        .description("Defaults to the current channel")
+                        .kind(CommandOptionType::Channel)
+                        .required(false)
+                })
+        })
+        .create_option(|option| {
+            option
+                .name("ratelimit")
+                .description("Set the max reactions per user per hour")
+                .kind(CommandOptionType::SubCommand)
+                .create_sub_option(|option| {
+                    option
+                        .name("limit")
+                        .description("Reactions per hour, 0 for unlimited")
+                        .kind(CommandOptionType::Integer)
+                        .required(true)
+                        .min_int_value(0)
+                        .max_int_value(1000)
+                })
+        })
+}
@@ -1,28 +1,201 @@
+use std::fs::File;
+
 use serenity::{
-    model::prelude::interaction::{
-        application_command::ApplicationCommandInteraction, autocomplete::AutocompleteInteraction,
-        InteractionResponseType,
+    builder::CreateComponents,
+    model::prelude::{
+        command::CommandOptionType,
+        component::ButtonStyle,
+        interaction::{
+            application_command::{ApplicationCommandInteraction, CommandDataOption},
+            autocomplete::AutocompleteInteraction,
+            message_component::MessageComponentInteraction,
+            InteractionResponseType,
+        },
+        AttachmentType, MessageFlags, ReactionType,
     },
     prelude::Context,
 };
 
 use crate::commands;
 
+// Commands that can take longer than the 3 seconds Discord waits for a response
+const DEFERRED_COMMANDS: &[&str] = &[
+    "react",
+    "createreaction",
+    "emotes",
+    "sticker",
+    "meme",
+    "image",
+];
+// Deferred subcommands that answer privately, so their deferral is hidden as well.
+// Commands without subcommands are listed with an empty subcommand.
+const HIDDEN_SUBCOMMANDS: &[(&str, &str)] = &[
+    ("react", ""),
+    ("emotes", "browse"),
+    ("emotes", "stats"),
+    ("emotes", "sync"),
+    ("emotes", "dupes"),
+    ("emotes", "export"),
+    ("sticker", "send"),
+];
+// Components that can take longer, their response is sent as a followup
+const DEFERRED_COMPONENTS: &[&str] = &["proposal", "reactwith"];
+
 pub enum Response {
     Shown(String),
     Hidden(String),
+    /// Hidden message with a confirm button using the custom id, and a cancel button
+    Confirm(String, String),
+    /// Message with a file attached
+    File {
+        content: String,
+        filename: String,
+        data: Vec<u8>,
+        hidden: bool,
+    },
+    /// Hidden message with a select menu using the custom id, any number of choices can be picked
+    Select(String, String, Vec<SelectChoice>),
+}
+
+pub struct SelectChoice {
+    pub label: String,
+    pub value: String,
+    pub emoji: Option<ReactionType>,
+}
+
+pub fn is_admin(user: u64) -> bool {
+    let file = File::open("data/admin_users.json");
+    if let Ok(file) = file {
+        let admin_users: Vec<u64> = serde_json::from_reader(&file).expect("Unable to read file");
+        admin_users.contains(&user)
+    } else {
+        false
+    }
+}
+
+/// Cuts the content down to Discord's 2000 character message limit
+pub fn fit_message(mut content: String) -> String {
+    if let Some((end, _)) = content.char_indices().nth(1990) {
+        content.truncate(end);
+        content.push_str("...");
+    }
+    content
+}
+
+/// What the user has typed so far in the focused autocomplete option
+pub fn focused_value(options: &[CommandDataOption]) -> String {
+    for option in options {
+        if option.focused {
+            return option
+                .value
+                .as_ref()
+                .and_then(|value| value.as_str())
+                .unwrap_or_default()
+                .to_string();
+        }
+        let value = focused_value(&option.options);
+        if !value.is_empty() {
+            return value;
+        }
+    }
+    String::new()
 }
 
 pub async fn command_handler(ctx: &Context, command: &ApplicationCommandInteraction) {
+    let deferred = DEFERRED_COMMANDS.contains(&command.data.name.as_str());
+    let subcommand = command
+        .data
+        .options
+        .first()
+        .filter(|option| {
+            matches!(
+                option.kind,
+                CommandOptionType::SubCommand | CommandOptionType::SubCommandGroup
+            )
+        })
+        .map(|option| option.name.as_str())
+        .unwrap_or_default();
+    let hidden = HIDDEN_SUBCOMMANDS.contains(&(command.data.name.as_str(), subcommand));
+    if deferred {
+        let result = if hidden {
+            command.defer_ephemeral(&ctx.http).await
+        } else {
+            command.defer(&ctx.http).await
+        };
+        if let Err(why) = result {
+            println!("Cannot defer slash command: {}", why);
+            return;
+        }
+    }
     let res = match command.data.name.as_str() {
         "react" => commands::react::run(&command.data.options, &ctx, &command).await,
         "createreaction" => {
-            commands::create_reaction::run(&command.data.options, &ctx, &command).await
+            commands::create_reaction::run(&command.data.options, ctx, command).await
         }
-        "pardon" => commands::pardon::run(&command.data.options, &command).await,
+        "pardon" => commands::pardon::run(&command.data.options, ctx, command).await,
+        "friday" => commands::friday::run(&command.data.options, ctx, command).await,
+        "calendar" => commands::calendar::run(&command.data.options, command).await,
+        "deletereaction" => commands::delete_reaction::run(&command.data.options, command).await,
+        "emotes" => commands::emotes::run(&command.data.options, ctx, command).await,
+        "reactionroles" => commands::reaction_roles::run(&command.data.options, ctx, command).await,
+        "starboard" => commands::starboard::run(&command.data.options, ctx, command).await,
+        "sticker" => commands::sticker::run(&command.data.options, ctx, command).await,
+        "meme" => commands::meme::run(&command.data.options, ctx).await,
+        "image" => commands::image::run(&command.data.options, ctx, command).await,
+        "React with emote" => commands::react_with::run(command).await,
         _ => Response::Hidden("Unknown command".to_string()),
     };
-    response_handler(&ctx, &command, &res).await;
+    if deferred {
+        deferred_response_handler(ctx, command, &res, hidden).await;
+    } else {
+        response_handler(ctx, command, &res).await;
+    }
+}
+
+fn confirm_buttons<'a>(
+    components: &'a mut CreateComponents,
+    custom_id: &str,
+) -> &'a mut CreateComponents {
+    components.create_action_row(|row| {
+        row.create_button(|button| {
+            button
+                .label("Confirm")
+                .style(ButtonStyle::Danger)
+                .custom_id(custom_id)
+        })
+        .create_button(|button| {
+            button
+                .label("Cancel")
+                .style(ButtonStyle::Secondary)
+                .custom_id("cancel")
+        })
+    })
+}
+
+fn select_menu<'a>(
+    components: &'a mut CreateComponents,
+    custom_id: &str,
+    choices: &[SelectChoice],
+) -> &'a mut CreateComponents {
+    components.create_action_row(|row| {
+        row.create_select_menu(|menu| {
+            menu.custom_id(custom_id)
+                .min_values(1)
+                .max_values(choices.len() as u64)
+                .options(|options| {
+                    for choice in choices {
+                        options.create_option(|option| {
+                            option.label(&choice.label).value(&choice.value);
+                            if let Some(emoji) = &choice.emoji {
+                                option.emoji(emoji.clone());
+                            }
+                            option
+                        });
+                    }
+                    options
+                })
+        })
+    })
 }
 
 async fn response_handler(ctx: &Context, command: &ApplicationCommandInteraction, res: &Response) {
@@ -33,6 +206,25 @@ async fn response_handler(ctx: &Context, command: &ApplicationCommandInteraction
                 .interaction_response_data(|message| match res {
                     Response::Shown(content) => message.content(content),
                     Response::Hidden(content) => message.content(content).ephemeral(true),
+                    Response::Confirm(content, custom_id) => message
+                        .content(content)
+                        .ephemeral(true)
+                        .components(|components| confirm_buttons(components, custom_id)),
+                    Response::File {
+                        content,
+                        filename,
+                        data,
+                        hidden,
+                    } => message.content(content).ephemeral(*hidden).add_file(
+                        AttachmentType::Bytes {
+                            data: data.into(),
+                            filename: filename.to_string(),
+                        },
+                    ),
+                    Response::Select(content, custom_id, choices) => message
+                        .content(content)
+                        .ephemeral(true)
+                        .components(|components| select_menu(components, custom_id, choices)),
                 })
         })
         .await
@@ -41,10 +233,188 @@ async fn response_handler(ctx: &Context, command: &ApplicationCommandInteraction
     }
 }
 
+/// Responses as public or hidden as the deferral replace it, the others get a followup
+async fn deferred_response_handler(
+    ctx: &Context,
+    command: &ApplicationCommandInteraction,
+    res: &Response,
+    hidden: bool,
+) {
+    let result = match res {
+        Response::Shown(content) | Response::Hidden(content)
+            if hidden == matches!(res, Response::Hidden(_)) =>
+        {
+            command
+                .edit_original_interaction_response(&ctx.http, |message| message.content(content))
+                .await
+                .map(|_| ())
+        }
+        // Files can't be added by editing, so they also get a followup
+        _ => {
+            if let Err(why) = command
+                .delete_original_interaction_response(&ctx.http)
+                .await
+            {
+                println!("Cannot delete deferred response: {}", why);
+            }
+            command
+                .create_followup_message(&ctx.http, |message| match res {
+                    Response::Confirm(content, custom_id) => message
+                        .content(content)
+                        .ephemeral(true)
+                        .components(|components| confirm_buttons(components, custom_id)),
+                    Response::File {
+                        content,
+                        filename,
+                        data,
+                        hidden,
+                    } => message.content(content).ephemeral(*hidden).add_file(
+                        AttachmentType::Bytes {
+                            data: data.into(),
+                            filename: filename.to_string(),
+                        },
+                    ),
+                    Response::Select(content, custom_id, choices) => message
+                        .content(content)
+                        .ephemeral(true)
+                        .components(|components| select_menu(components, custom_id, choices)),
+                    Response::Shown(content) => message.content(content),
+                    Response::Hidden(content) => message.content(content).ephemeral(true),
+                })
+                .await
+                .map(|_| ())
+        }
+    };
+    if let Err(why) = result {
+        println!("Cannot respond to slash command: {}", why);
+    }
+}
+
+pub async fn component_handler(ctx: &Context, component: &MessageComponentInteraction) {
+    // Custom ids look like "command:data"
+    let custom_id = component.data.custom_id.as_str();
+    let (kind, data) = custom_id.split_once(':').unwrap_or((custom_id, ""));
+    let deferred = DEFERRED_COMPONENTS.contains(&kind);
+    if deferred {
+        if let Err(why) = component.defer(&ctx.http).await {
+            println!("Cannot defer component: {}", why);
+            return;
+        }
+    }
+    let res = match kind {
+        "cancel" => Response::Hidden("Cancelled".to_string()),
+        "deletereaction" => commands::delete_reaction::confirm(data, ctx, component).await,
+        "reactionrole" => commands::reaction_roles::toggle(data, ctx, component).await,
+        "reactwith" => commands::react_with::select(data, ctx, component).await,
+        "proposal" => commands::emotes::proposals::review(data, ctx, component).await,
+        _ => Response::Hidden("Unknown component".to_string()),
+    };
+    if deferred {
+        deferred_component_response_handler(ctx, component, &res).await;
+    } else {
+        component_response_handler(ctx, component, &res).await;
+    }
+}
+
+/// The deferred component left its message as it was, so hidden prompts are replaced
+/// by the response and public messages get a new message
+async fn deferred_component_response_handler(
+    ctx: &Context,
+    component: &MessageComponentInteraction,
+    res: &Response,
+) {
+    let (content, shown) = match res {
+        Response::Shown(content) => (content, true),
+        Response::Hidden(content)
+        | Response::Confirm(content, _)
+        | Response::Select(content, _, _) => (content, false),
+        Response::File {
+            content, hidden, ..
+        } => (content, !hidden),
+    };
+    let ephemeral = component
+        .message
+        .flags
+        .is_some_and(|flags| flags.contains(MessageFlags::EPHEMERAL));
+    if ephemeral {
+        if let Err(why) = component
+            .edit_original_interaction_response(&ctx.http, |message| {
+                message.content(content).components(|components| components)
+            })
+            .await
+        {
+            println!("Cannot respond to component: {}", why);
+        }
+        if !shown {
+            return;
+        }
+    }
+    if let Err(why) = component
+        .create_followup_message(&ctx.http, |message| {
+            message.content(content).ephemeral(!shown)
+        })
+        .await
+    {
+        println!("Cannot respond to component: {}", why);
+    }
+}
+
+/// Hidden prompts are replaced by the response, public messages get a new response
+async fn component_response_handler(
+    ctx: &Context,
+    component: &MessageComponentInteraction,
+    res: &Response,
+) {
+    let (content, shown) = match res {
+        Response::Shown(content) => (content, true),
+        Response::Hidden(content)
+        | Response::Confirm(content, _)
+        | Response::Select(content, _, _) => (content, false),
+        Response::File {
+            content, hidden, ..
+        } => (content, !hidden),
+    };
+    let ephemeral = component
+        .message
+        .flags
+        .is_some_and(|flags| flags.contains(MessageFlags::EPHEMERAL));
+    let result = component
+        .create_interaction_response(&ctx.http, |response| {
+            if ephemeral {
+                response
+                    .kind(InteractionResponseType::UpdateMessage)
+                    .interaction_response_data(|message| {
+                        message.content(content).components(|components| components)
+                    })
+            } else {
+                response
+                    .kind(InteractionResponseType::ChannelMessageWithSource)
+                    .interaction_response_data(|message| message.content(content).ephemeral(!shown))
+            }
+        })
+        .await;
+    if let Err(why) = result {
+        println!("Cannot respond to component: {}", why);
+        return;
+    }
+    // The replaced prompt was hidden, so shown responses are sent again for everyone
+    if ephemeral && shown {
+        if let Err(why) = component
+            .create_followup_message(&ctx.http, |message| message.content(content))
+            .await
+        {
+            println!("Cannot send followup message: {}", why);
+        }
+    }
+}
+
 pub async fn autocomplete_handler(ctx: &Context, autocomplete: &AutocompleteInteraction) {
     println!("Hello");
     match autocomplete.data.name.as_str() {
-        "react" => commands::react::send_autocomplete(autocomplete, &ctx).await,
+        "react" | "deletereaction" | "emotes" | "reactionroles" | "starboard" | "meme" => {
+            commands::react::send_autocomplete(autocomplete, ctx).await
+        }
+        "sticker" => commands::sticker::send_autocomplete(autocomplete, ctx).await,
         _ => {}
     };
 }
@@ -0,0 +1,275 @@
+use serenity::builder::{CreateApplicationCommand, CreateApplicationCommandOption};
+use serenity::model::prelude::command::CommandOptionType;
+use serenity::model::prelude::interaction::application_command::{
+    ApplicationCommandInteraction, CommandDataOption, CommandDataOptionValue,
+};
+use serenity::model::prelude::Message;
+use serenity::prelude::Context;
+
+use crate::commands::create_reaction::create_or_propose;
+use crate::commands::emotes::string_option;
+use crate::commands::handler::Response;
+use crate::commands::react::{find_target, parse_message, Target};
+use crate::imaging::emote::prepare_emote;
+use crate::imaging::transform::{transform_image, Transform};
+use crate::imaging::{download_image, process};
+
+/// The first image attached or embedded in the message
+fn message_image(message: &Message) -> Option<String> {
+    let attachment = message.attachments.iter().find(|attachment| {
+        attachment
+            .content_type
+            .as_deref()
+            .is_some_and(|kind| kind.starts_with("image/"))
+    });
+    if let Some(attachment) = attachment {
+        return Some(attachment.url.clone());
+    }
+    message.embeds.iter().find_map(|embed| {
+        embed
+            .image
+            .as_ref()
+            .map(|image| image.url.clone())
+            .or_else(|| {
+                embed
+                    .thumbnail
+                    .as_ref()
+                    .map(|thumbnail| thumbnail.url.clone())
+            })
+    })
+}
+
+/// Finds the image to work on from an attachment, a user's avatar, a message or a reply
+async fn source_image(
+    options: &[CommandDataOption],
+    ctx: &Context,
+    command: &ApplicationCommandInteraction,
+) -> Result<Vec<u8>, String> {
+    let mut target = None;
+    for option in options {
+        match (option.name.as_str(), option.resolved.as_ref()) {
+            ("image", Some(CommandDataOptionValue::Attachment(attachment))) => {
+                return download_image(&attachment.url).await
+            }
+            ("user", Some(CommandDataOptionValue::User(user, _))) => {
+                return download_image(&user.face()).await
+            }
+            ("message", Some(CommandDataOptionValue::String(value))) => {
+                let (channel, id) = parse_message(value, command.channel_id)
+                    .ok_or_else(|| "Please provide a valid message id or link".to_string())?;
+                target = Some(Target::Message(channel, id));
+            }
+            ("reply", Some(CommandDataOptionValue::Boolean(true))) => {
+                target = Some(Target::Reply);
+            }
+            _ => {}
+        }
+    }
+    let target = target.ok_or_else(|| {
+        "Please provide an image, a user, a message or the message you replied to".to_string()
+    })?;
+    let message = find_target(ctx, command.channel_id, command.user.id, target).await?;
+    let url = message_image(&message).ok_or_else(|| "That message has no image".to_string())?;
+    download_image(&url).await
+}
+
+/// Turns the image into an emote, and uploads it if a name is given
+async fn make_emote(
+    options: &[CommandDataOption],
+    bytes: Vec<u8>,
+    ctx: &Context,
+    command: &ApplicationCommandInteraction,
+) -> Response {
+    // The upload processes the image again, which is quick once it's emote sized
+    let image = match process(move || prepare_emote(&bytes)).await {
+        Ok(image) => image,
+        Err(why) => return Response::Hidden(why),
+    };
+    let name = match string_option(options, "name") {
+        Some(name) => name,
+        None => {
+            return Response::File {
+                content: String::new(),
+                filename: format!("emote.{}", if image.animated { "gif" } else { "png" }),
+                data: image.data,
+                hidden: false,
+            }
+        }
+    };
+    create_or_propose(name, image.data, ctx, command).await
+}
+
+pub async fn run(
+    options: &[CommandDataOption],
+    ctx: &Context,
+    command: &ApplicationCommandInteraction,
+) -> Response {
+    let subcommand = match options.first() {
+        Some(subcommand) => subcommand,
+        None => return Response::Hidden("Please provide a subcommand".to_string()),
+    };
+    let integer = |name: &str| {
+        subcommand
+            .options
+            .iter()
+            .find(|option| option.name == name)
+            .and_then(|option| match option.resolved.as_ref() {
+                Some(CommandDataOptionValue::Integer(value)) => Some(*value),
+                _ => None,
+            })
+    };
+    let transform = match subcommand.name.as_str() {
+        "deepfry" => Transform::Deepfry,
+        "jpeg" => Transform::Jpeg,
+        "invert" => Transform::Invert,
+        "pixelate" => Transform::Pixelate(integer("size").unwrap_or(8).clamp(2, 64) as u32),
+        "rotate" => Transform::Rotate(integer("degrees").unwrap_or(90)),
+        "speechbubble" => Transform::SpeechBubble,
+        "emote" => {
+            return match source_image(&subcommand.options, ctx, command).await {
+                Ok(bytes) => make_emote(&subcommand.options, bytes, ctx, command).await,
+                Err(why) => Response::Hidden(why),
+            }
+        }
+        _ => return Response::Hidden("Unknown subcommand".to_string()),
+    };
+    let bytes = match source_image(&subcommand.options, ctx, command).await {
+        Ok(bytes) => bytes,
+        Err(why) => return Response::Hidden(why),
+    };
+    match process(move || transform_image(&bytes, transform)).await {
+        Ok((data, animated)) => Response::File {
+            content: String::new(),
+            filename: format!(
+                "{}.{}",
+                subcommand.name,
+                if animated { "gif" } else { "png" }
+            ),
+            data,
+            hidden: false,
+        },
+        Err(why) => Response::Hidden(why),
+    }
+}
+
+/// Adds the options every subcommand takes to pick the image
+fn source_options(
+    option: &mut CreateApplicationCommandOption,
+) -> &mut CreateApplicationCommandOption {
+    option
+        .create_sub_option(|option| {
+            option
+                .name("image")
+                .description("Image or gif to use")
+                .kind(CommandOptionType::Attachment)
+                .required(false)
+        })
+        .create_sub_option(|option| {
+            option
+                .name("user")
+                .description("Use the avatar of this user")
+                .kind(CommandOptionType::User)
+                .required(false)
+        })
+        .create_sub_option(|option| {
+            option
+                .name("message")
+                .description("Use the image of this message, by id or link")
+                .kind(CommandOptionType::String)
+                .required(false)
+        })
+        .create_sub_option(|option| {
+            option
+                .name("reply")
+                .description("Use the image of the message you last replied to")
+                .kind(CommandOptionType::Boolean)
+                .required(false)
+        })
+}
+
+pub fn register(command: &mut CreateApplicationCommand) -> &mut CreateApplicationCommand {
+    command
+        .name("image")
+        .description("Edit an image, avatar or the image of a message")
+        .create_option(|option| {
+            source_options(
+                option
+                    .name("deepfry")
+                    .description("Oversaturate and crush the image")
+                    .kind(CommandOptionType::SubCommand),
+            )
+        })
+        .create_option(|option| {
+            source_options(
+                option
+                    .name("jpeg")
+                    .description("Compress the image until it falls apart")
+                    .kind(CommandOptionType::SubCommand),
+            )
+        })
+        .create_option(|option| {
+            source_options(
+                option
+                    .name("invert")
+                    .description("Invert the colors of the image")
+                    .kind(CommandOptionType::SubCommand),
+            )
+        })
+        .create_option(|option| {
+            source_options(
+                option
+                    .name("pixelate")
+                    .description("Turn the image into big pixels")
+                    .kind(CommandOptionType::SubCommand)
+                    .create_sub_option(|option| {
+                        option
+                            .name("size")
+                            .description("Size of the pixels, defaults to 8")
+                            .kind(CommandOptionType::Integer)
+                            .min_int_value(2)
+                            .max_int_value(64)
+                            .required(false)
+                    }),
+            )
+        })
+        .create_option(|option| {
+            source_options(
+                option
+                    .name("rotate")
+                    .description("Rotate the image clockwise")
+                    .kind(CommandOptionType::SubCommand)
+                    .create_sub_option(|option| {
+                        option
+                            .name("degrees")
+                            .description("Angle to rotate by, defaults to 90")
+                            .kind(CommandOptionType::Integer)
+                            .min_int_value(-360)
+                            .max_int_value(360)
+                            .required(false)
+                    }),
+            )
+        })
+        .create_option(|option| {
+            source_options(
+                option
+                    .name("speechbubble")
+                    .description("Cut a speech bubble out of the top of the image")
+                    .kind(CommandOptionType::SubCommand),
+            )
+        })
+        .create_option(|option| {
+            source_options(
+                option
+                    .name("emote")
+                    .description("Make the image an emote, and add it if a name is given")
+                    .kind(CommandOptionType::SubCommand)
+                    .create_sub_option(|option| {
+                        option
+                            .name("name")
+                            .description("Name to add the emote as")
+                            .kind(CommandOptionType::String)
+                            .required(false)
+                    }),
+            )
+        })
+}
@@ -0,0 +1,96 @@
+use serenity::builder::CreateApplicationCommand;
+use serenity::model::prelude::command::CommandOptionType;
+use serenity::model::prelude::interaction::application_command::{
+    CommandDataOption, CommandDataOptionValue,
+};
+use serenity::model::prelude::ReactionType;
+use serenity::prelude::Context;
+
+use crate::commands::emotes::{download_emote, string_option};
+use crate::commands::handler::Response;
+use crate::commands::react::resolve_emote;
+use crate::imaging::meme::render_meme;
+use crate::imaging::{download_image, process};
+
+pub async fn run(options: &[CommandDataOption], ctx: &Context) -> Response {
+    let top = string_option(options, "top")
+        .unwrap_or_default()
+        .to_string();
+    let bottom = string_option(options, "bottom")
+        .unwrap_or_default()
+        .to_string();
+    if top.trim().is_empty() && bottom.trim().is_empty() {
+        return Response::Hidden("Please provide a top or bottom text".to_string());
+    }
+    let attachment = options
+        .iter()
+        .find(|option| option.name == "image")
+        .and_then(|option| match option.resolved.as_ref() {
+            Some(CommandDataOptionValue::Attachment(attachment)) => Some(attachment),
+            _ => None,
+        });
+    // The image is either the attachment or a custom emote
+    let bytes = match (attachment, string_option(options, "emote")) {
+        (Some(attachment), _) => match download_image(&attachment.url).await {
+            Ok(bytes) => bytes,
+            Err(why) => return Response::Hidden(why),
+        },
+        (None, Some(emote)) => match resolve_emote(ctx, emote).await {
+            Ok(ReactionType::Custom { animated, id, .. }) => {
+                match download_emote(&id.to_string(), animated).await {
+                    Ok(bytes) => bytes,
+                    Err(why) => return Response::Hidden(why),
+                }
+            }
+            Ok(_) => return Response::Hidden("Please pick a custom emote".to_string()),
+            Err(why) => return Response::Hidden(why),
+        },
+        (None, None) => return Response::Hidden("Please provide an image or an emote".to_string()),
+    };
+    match process(move || render_meme(&bytes, &top, &bottom)).await {
+        Ok((data, animated)) => Response::File {
+            content: String::new(),
+            filename: format!("meme.{}", if animated { "gif" } else { "png" }),
+            data,
+            hidden: false,
+        },
+        Err(why) => Response::Hidden(why),
+    }
+}
+
+pub fn register(command: &mut CreateApplicationCommand) -> &mut CreateApplicationCommand {
+    command
+        .name("meme")
+        .description("Caption an image or emote with top and bottom text")
+        .create_option(|option| {
+            option
+                .name("top")
+                .description("Text at the top")
+                .kind(CommandOptionType::String)
+                .max_length(200)
+                .required(false)
+        })
+        .create_option(|option| {
+            option
+                .name("bottom")
+                .description("Text at the bottom")
+                .kind(CommandOptionType::String)
+                .max_length(200)
+                .required(false)
+        })
+        .create_option(|option| {
+            option
+                .name("image")
+                .description("Image or gif to caption")
+                .kind(CommandOptionType::Attachment)
+                .required(false)
+        })
+        .create_option(|option| {
+            option
+                .name("emote")
+                .description("Emote to caption instead of an image")
+                .kind(CommandOptionType::String)
+                .required(false)
+                .set_autocomplete(true)
+        })
+}
@@ -1,6 +1,17 @@
+pub mod calendar;
 pub mod chess;
 pub mod create_reaction;
+pub mod delete_reaction;
+pub mod emotes;
+pub mod friday;
 pub mod handler;
+pub mod image;
+pub mod meme;
 pub mod music;
 pub mod pardon;
 pub mod react;
+pub mod react_with;
+pub mod reaction_roles;
+pub mod starboard;
+pub mod sticker;
+pub mod usage;
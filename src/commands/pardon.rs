@@ -1,5 +1,6 @@
-use std::fs::File;
-
+use chrono::Utc;
+use lazy_static::lazy_static;
+use serde::{Deserialize, Serialize};
 use serenity::{
     builder::CreateApplicationCommand,
     model::prelude::{
@@ -7,50 +8,121 @@ use serenity::{
         interaction::application_command::{
             ApplicationCommandInteraction, CommandDataOption, CommandDataOptionValue,
         },
+        User,
     },
+    prelude::Context,
 };
 
-use crate::commands::handler::Response;
-use crate::friday::friday::add_user;
+use crate::commands::handler::{is_admin, Response};
+use crate::friday::friday::{add_permanent_user, add_user};
+use crate::store::Store;
+
+lazy_static! {
+    pub static ref PARDON_LOG: Store<Vec<PardonLog>> = Store::load("data/pardon_log.json");
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct PardonLog {
+    moderator: u64,
+    user: u64,
+    reason: Option<String>,
+    permanent: bool,
+    time: String,
+}
+
+async fn log_pardon(entry: PardonLog) {
+    println!("Pardon: {:?}", entry);
+    PARDON_LOG.update(|log| log.push(entry)).await;
+}
 
 pub async fn run(
     options: &[CommandDataOption],
+    ctx: &Context,
     command: &ApplicationCommandInteraction,
 ) -> Response {
-    let user = command.member.as_ref().unwrap().user.id.0;
-    let file = File::open("data/admin_users.json");
-    if let Ok(file) = file {
-        let admin_users: Vec<u64> = serde_json::from_reader(&file).expect("Unable to read file");
-        if !admin_users.contains(&user) {
-            return Response::Hidden("You are not an admin".to_string());
-        }
-    } else {
+    let moderator = &command.user;
+    if !is_admin(moderator.id.0) {
         return Response::Hidden("You are not an admin".to_string());
     }
-    let user = options
-        .get(0)
-        .expect("Expected user option")
-        .resolved
-        .as_ref()
-        .expect("Expected user object");
-
-    if let CommandDataOptionValue::User(u, _) = user {
-        add_user(u.id.0);
-        return Response::Shown(format!("Added user {}", u.name));
+    let mut user: Option<&User> = None;
+    let mut reason: Option<String> = None;
+    let mut permanent = false;
+    for option in options {
+        match (option.name.as_str(), option.resolved.as_ref()) {
+            ("user", Some(CommandDataOptionValue::User(u, _))) => user = Some(u),
+            ("reason", Some(CommandDataOptionValue::String(r))) => reason = Some(r.to_string()),
+            ("duration", Some(CommandDataOptionValue::String(d))) => permanent = d == "permanent",
+            _ => {}
+        }
+    }
+    let user = match user {
+        Some(user) => user,
+        None => return Response::Hidden("Please provide a valid user".to_string()),
+    };
+
+    let duration = if permanent {
+        add_permanent_user(user.id.0).await;
+        "permanently"
+    } else if add_user(user.id.0).await {
+        "for this Friday"
     } else {
-        return Response::Hidden("Please provide a valid name".to_string());
+        "for the next Friday"
+    };
+    log_pardon(PardonLog {
+        moderator: moderator.id.0,
+        user: user.id.0,
+        reason: reason.clone(),
+        permanent,
+        time: Utc::now().to_rfc3339(),
+    })
+    .await;
+
+    let reason_text = match &reason {
+        Some(reason) => format!(" Reason: {}", reason),
+        None => String::new(),
     };
+    if let Err(why) = user
+        .direct_message(&ctx.http, |message| {
+            message.content(format!(
+                "You have been pardoned {} by {}.{}",
+                duration, moderator.name, reason_text
+            ))
+        })
+        .await
+    {
+        println!("Unable to DM pardoned user: {}", why);
+    }
+    Response::Shown(format!(
+        "Pardoned {} {}.{}",
+        user.name, duration, reason_text
+    ))
 }
 
 pub fn register(command: &mut CreateApplicationCommand) -> &mut CreateApplicationCommand {
     command
-        .name("user")
-        .description("Creates a reaction")
+        .name("pardon")
+        .description("Pardon a user from Friday reactions")
         .create_option(|option| {
             option
-                .name("name")
+                .name("user")
                 .description("Choose a user to pardon")
                 .kind(CommandOptionType::User)
                 .required(true)
         })
+        .create_option(|option| {
+            option
+                .name("reason")
+                .description("Why the user is pardoned")
+                .kind(CommandOptionType::String)
+                .required(false)
+        })
+        .create_option(|option| {
+            option
+                .name("duration")
+                .description("Defaults to this or the next Friday")
+                .kind(CommandOptionType::String)
+                .required(false)
+                .add_string_choice("This or the next Friday", "friday")
+                .add_string_choice("Permanent", "permanent")
+        })
 }
@@ -1,6 +1,7 @@
-use std::fs::File;
-use std::io::Write;
+use std::collections::HashMap;
 
+use chrono::Utc;
+use lazy_static::lazy_static;
 use serde::{Deserialize, Serialize};
 use serenity::builder::CreateApplicationCommand;
 use serenity::model::application::interaction::autocomplete::AutocompleteInteraction;
@@ -8,29 +9,301 @@ use serenity::model::prelude::command::CommandOptionType;
 use serenity::model::prelude::interaction::application_command::{
     ApplicationCommandInteraction, CommandDataOption, CommandDataOptionValue,
 };
-use serenity::model::prelude::ReactionType;
+use serenity::model::prelude::{ChannelId, GuildId, Message, MessageId, ReactionType, UserId};
 use serenity::prelude::Context;
 
-use crate::commands::handler::Response;
+use crate::commands::create_reaction::{get_home_channel, valid_name};
+use crate::commands::emotes::storage::find_in_storage;
+use crate::commands::handler::{focused_value, Response};
+use crate::commands::usage::{record_use, EmoteUsage, Source, USAGE};
+use crate::store::Store;
 
-#[derive(Deserialize, Serialize, Debug)]
+// Discord allows at most 25 autocomplete choices
+const MAX_CHOICES: usize = 25;
+
+lazy_static! {
+    pub static ref EMOTES: Store<Vec<Emote>> = Store::load("data/emotes.json");
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, Default)]
 pub struct Emote {
     pub name: String,
     pub emote: String,
+    /// Storage guild holding the emote, older entries are in the home guild
+    #[serde(default)]
+    pub guild: Option<u64>,
+    /// Animated emotes are mentioned as `<a:name:id>`, older entries are fixed by the sync
+    #[serde(default)]
+    pub animated: bool,
+    /// Other names the emote can be found by
+    #[serde(default)]
+    pub aliases: Vec<String>,
+    /// Categories for browsing, these are searched by autocomplete as well
+    #[serde(default)]
+    pub tags: Vec<String>,
+    /// Perceptual hash of the image, missing ones are filled in by the sync
+    #[serde(default)]
+    pub hash: Option<u64>,
+    /// Set when the image couldn't be downloaded or decoded for hashing
+    #[serde(default)]
+    pub unhashable: bool,
+}
+
+impl Emote {
+    pub fn guild_id(&self) -> GuildId {
+        GuildId(self.guild.unwrap_or_else(|| get_home_channel().guild_id))
+    }
+
+    /// The emote formatted for use in messages and reactions
+    pub fn mention(&self) -> String {
+        let prefix = if self.animated { "a" } else { "" };
+        format!("<{}:{}:{}>", prefix, self.name, self.emote)
+    }
+
+    /// Whether the name or one of the aliases is `name`
+    pub fn answers_to(&self, name: &str) -> bool {
+        self.name == name || self.aliases.iter().any(|alias| alias == name)
+    }
+}
+
+/// Finds an emote by its name, an alias or its id
+pub fn find_emote<'a>(emotes: &'a [Emote], query: &str) -> Option<&'a Emote> {
+    emotes
+        .iter()
+        .find(|e| e.answers_to(query) || e.emote == query)
+}
+
+// Discord allows at most 20 different reactions on a message
+const MAX_REACTIONS: usize = 20;
+
+/// Keycap emojis like 1️⃣ and #️⃣ start with a plain digit, `#` or `*`
+fn is_keycap(query: &str) -> bool {
+    let mut chars = query.chars();
+    matches!(chars.next(), Some('0'..='9' | '#' | '*'))
+        && matches!(chars.as_str(), "\u{fe0f}\u{20e3}" | "\u{20e3}")
+}
+
+/// Resolves one emote from a list, as a registry emote, a custom emote or a Unicode emoji
+pub async fn resolve_emote(ctx: &Context, query: &str) -> Result<ReactionType, String> {
+    let found = find_emote(&EMOTES.read().await, query).map(|e| e.mention());
+    if let Some(Ok(emote)) = found.map(ReactionType::try_from) {
+        return Ok(emote);
+    }
+    if query.starts_with('<') {
+        return ReactionType::try_from(query).map_err(|_| format!("Invalid emote '{}'", query));
+    }
+    // Anything without letters or digits is taken as a Unicode emoji
+    if is_keycap(query) || !query.chars().any(|c| c.is_ascii_alphanumeric()) {
+        return Ok(ReactionType::Unicode(query.to_string()));
+    }
+    // Fall back to emotes in the storage guilds missing from the registry,
+    // which takes a request per guild so only names that could be emotes are looked up
+    if valid_name(query) {
+        let found = find_in_storage(ctx, query).await;
+        if let Some(Ok(emote)) = found.map(|emoji| ReactionType::try_from(emoji.to_string())) {
+            return Ok(emote);
+        }
+    }
+    Err(format!("Unable to find emote '{}'", query))
+}
+
+/// Resolves a comma separated list of emotes, keeping their order
+pub async fn resolve_emotes(ctx: &Context, list: &str) -> Result<Vec<ReactionType>, String> {
+    let mut emotes = Vec::new();
+    for query in list.split(',').map(str::trim).filter(|q| !q.is_empty()) {
+        emotes.push(resolve_emote(ctx, query).await?);
+    }
+    Ok(emotes)
+}
+
+/// Emojis that can stand in for a character, the regional indicator letter comes first
+fn letter_emojis(c: char) -> Vec<String> {
+    let c = c.to_ascii_lowercase();
+    let mut emojis = Vec::new();
+    if c.is_ascii_lowercase() {
+        let indicator = char::from_u32(0x1F1E6 + (c as u32 - 'a' as u32)).unwrap();
+        emojis.push(indicator.to_string());
+    }
+    let fallbacks: &[&str] = match c {
+        'a' => &["🅰️"],
+        'b' => &["🅱️"],
+        'c' => &["©️"],
+        'e' => &["📧"],
+        'i' => &["ℹ️"],
+        'm' => &["Ⓜ️"],
+        'o' => &["🅾️", "⭕"],
+        'p' => &["🅿️"],
+        'r' => &["®️"],
+        'x' => &["❌", "✖️"],
+        '!' => &["❗", "❕"],
+        '?' => &["❓", "❔"],
+        _ => &[],
+    };
+    if c.is_ascii_digit() {
+        emojis.push(format!("{}\u{fe0f}\u{20e3}", c));
+    }
+    emojis.extend(fallbacks.iter().map(|e| e.to_string()));
+    emojis
+}
+
+/// Spells a word with letter emojis, using fallbacks for repeated letters
+/// since a message can only have each reaction once
+pub fn spell(word: &str, used: &[ReactionType]) -> Result<Vec<ReactionType>, String> {
+    let mut spelled: Vec<ReactionType> = Vec::new();
+    for c in word.chars().filter(|c| !c.is_whitespace()) {
+        let emoji = letter_emojis(c).into_iter().find(|emoji| {
+            let emoji = ReactionType::Unicode(emoji.clone());
+            !spelled.contains(&emoji) && !used.contains(&emoji)
+        });
+        match emoji {
+            Some(emoji) => spelled.push(ReactionType::Unicode(emoji)),
+            None if letter_emojis(c).is_empty() => return Err(format!("Unable to spell '{}'", c)),
+            None => return Err(format!("'{}' is used too often to spell", c)),
+        }
+    }
+    Ok(spelled)
+}
+
+/// Custom emotes are compared by id since reaction events can leave out the name
+pub fn same_emoji(a: &ReactionType, b: &ReactionType) -> bool {
+    match (a, b) {
+        (ReactionType::Custom { id: a, .. }, ReactionType::Custom { id: b, .. }) => a == b,
+        (ReactionType::Unicode(a), ReactionType::Unicode(b)) => {
+            a.trim_end_matches('\u{fe0f}') == b.trim_end_matches('\u{fe0f}')
+        }
+        _ => false,
+    }
+}
+
+/// Ids of every user that reacted to a message with the emote, except bots
+pub async fn reactors(
+    ctx: &Context,
+    channel: ChannelId,
+    message: u64,
+    emote: &ReactionType,
+) -> Result<Vec<u64>, String> {
+    let mut users = Vec::new();
+    let mut after: Option<UserId> = None;
+    loop {
+        let page = channel
+            .reaction_users(&ctx.http, message, emote.clone(), Some(100), after)
+            .await
+            .map_err(|why| why.to_string())?;
+        let done = page.len() < 100;
+        after = page.last().map(|user| user.id);
+        users.extend(
+            page.into_iter()
+                .filter(|user| !user.bot)
+                .map(|user| user.id.0),
+        );
+        if done {
+            return Ok(users);
+        }
+    }
+}
+
+// Discord returns at most 100 messages at a time
+const MAX_HISTORY: u64 = 100;
+
+/// The message to act on, like the one to react to
+pub enum Target {
+    /// The Nth message back in the channel, 1 is the latest
+    Relative(u64),
+    Message(ChannelId, MessageId),
+    /// The latest message of a user
+    Author(UserId),
+    /// The message the invoking user last replied to
+    Reply,
+}
+
+/// Parses a message id, or a message link like `https://discord.com/channels/guild/channel/message`
+pub fn parse_message(value: &str, channel: ChannelId) -> Option<(ChannelId, MessageId)> {
+    let value = value.trim();
+    if let Ok(id) = value.parse::<u64>() {
+        return Some((channel, MessageId(id)));
+    }
+    let path = value.split("/channels/").nth(1)?;
+    let ids: Vec<u64> = path
+        .trim_end_matches('/')
+        .split('/')
+        .map(|id| id.parse().ok())
+        .collect::<Option<_>>()?;
+    match ids.as_slice() {
+        [_, channel, message] => Some((ChannelId(*channel), MessageId(*message))),
+        _ => None,
+    }
+}
+
+async fn recent_messages(
+    ctx: &Context,
+    channel: ChannelId,
+    limit: u64,
+) -> Result<Vec<Message>, String> {
+    channel
+        .messages(&ctx.http, |m| m.limit(limit))
+        .await
+        .map_err(|_| "Unable to read the messages of this channel".to_string())
+}
+
+/// The message `relative` messages back, 1 being the latest.
+/// Messages come newest first, so the Nth one back is at index N - 1
+fn nth_back<T>(newest_first: Vec<T>, relative: u64) -> Option<T> {
+    let index = (relative as usize).checked_sub(1)?;
+    newest_first.into_iter().nth(index)
+}
+
+pub async fn find_target(
+    ctx: &Context,
+    channel: ChannelId,
+    user: UserId,
+    target: Target,
+) -> Result<Message, String> {
+    match target {
+        Target::Relative(relative) => {
+            let messages = recent_messages(ctx, channel, relative).await?;
+            nth_back(messages, relative)
+                .ok_or_else(|| format!("There aren't {} messages in this channel", relative))
+        }
+        Target::Message(channel, id) => channel
+            .message(&ctx.http, id)
+            .await
+            .map_err(|_| "Unable to find that message".to_string()),
+        Target::Author(author) => recent_messages(ctx, channel, MAX_HISTORY)
+            .await?
+            .into_iter()
+            .find(|message| message.author.id == author)
+            .ok_or_else(|| format!("<@{}> has no recent messages in this channel", author)),
+        Target::Reply => {
+            let reply = recent_messages(ctx, channel, MAX_HISTORY)
+                .await?
+                .into_iter()
+                .find(|message| message.author.id == user && message.message_reference.is_some())
+                .and_then(|message| message.message_reference)
+                .ok_or_else(|| "You haven't replied to a message here recently".to_string())?;
+            let id = reply
+                .message_id
+                .ok_or_else(|| "Unable to find the message you replied to".to_string())?;
+            reply
+                .channel_id
+                .message(&ctx.http, id)
+                .await
+                .map_err(|_| "Unable to find the message you replied to".to_string())
+        }
+    }
 }
 
 struct React {
-    emote: ReactionType,
-    id: Option<u64>,
-    relative: u64,
+    emotes: Vec<ReactionType>,
+    spell: Option<String>,
+    targets: Vec<Target>,
 }
 
 impl React {
     fn new() -> Self {
         Self {
-            emote: ReactionType::Unicode(String::new()),
-            id: None,
-            relative: 1,
+            emotes: Vec::new(),
+            spell: None,
+            targets: Vec::new(),
         }
     }
 }
@@ -42,68 +315,82 @@ pub async fn run(
 ) -> Response {
     let mut react = React::new();
     for option in options {
-        match option.name.as_str() {
-            "emote" => {
-                if let CommandDataOptionValue::String(emote) = &option.resolved.as_ref().unwrap() {
-                    let file = File::open("data/emotes.json").expect("Unable to open file");
-                    let emotes: Vec<Emote> =
-                        serde_json::from_reader(&file).expect("Unable to read file");
-                    let find_emote = emotes
-                        .iter()
-                        .find(|e| e.name == emote.to_string() || e.emote == emote.to_string());
-                    if let Some(emote) = find_emote {
-                        let emote_string = format!("<:{}:{}>", emote.name, emote.emote);
-                        if let Ok(e) = ReactionType::try_from(emote_string) {
-                            react.emote = e;
-                        } else {
-                            return Response::Hidden("Unable to find emote".to_string());
-                        }
-                    } else {
-                        return Response::Hidden("Unable to find emote".to_string());
-                    }
+        match (option.name.as_str(), option.resolved.as_ref()) {
+            ("emote", Some(CommandDataOptionValue::String(list))) => {
+                match resolve_emotes(ctx, list).await {
+                    Ok(emotes) => react.emotes = emotes,
+                    Err(why) => return Response::Hidden(why),
                 }
             }
-            "id" => {
-                if let CommandDataOptionValue::String(id) = &option.resolved.as_ref().unwrap() {
-                    let id = id.parse::<u64>();
-                    if let Ok(id) = id {
-                        react.id = Some(id);
-                    } else {
-                        return Response::Hidden("Invalid message ID".to_string());
+            ("spell", Some(CommandDataOptionValue::String(word))) => {
+                react.spell = Some(word.to_string());
+            }
+            ("message", Some(CommandDataOptionValue::String(value))) => {
+                match parse_message(value, command.channel_id) {
+                    Some((channel, id)) => react.targets.push(Target::Message(channel, id)),
+                    None => {
+                        return Response::Hidden("Please provide a message id or link".to_string())
                     }
                 }
             }
-            "relative" => {
-                if let CommandDataOptionValue::Integer(relative) =
-                    &option.resolved.as_ref().unwrap()
-                {
-                    react.relative = *relative as u64;
+            ("relative", Some(CommandDataOptionValue::Integer(relative))) => {
+                if !(1..=MAX_HISTORY as i64).contains(relative) {
+                    return Response::Hidden(format!(
+                        "Messages can be at most {} back",
+                        MAX_HISTORY
+                    ));
                 }
+                react.targets.push(Target::Relative(*relative as u64));
+            }
+            ("user", Some(CommandDataOptionValue::User(user, _))) => {
+                react.targets.push(Target::Author(user.id));
+            }
+            ("reply", Some(CommandDataOptionValue::Boolean(true))) => {
+                react.targets.push(Target::Reply);
             }
             _ => {}
         }
     }
-    let channel = command
-        .channel_id
-        .to_channel(&ctx.http)
-        .await
-        .unwrap()
-        .guild()
-        .unwrap();
-    let message;
-    if let Some(id) = react.id {
-        message = channel.message(&ctx.http, id).await.unwrap();
-    } else {
-        let messages = channel
-            .messages(&ctx.http, |m| m.limit(react.relative))
-            .await
-            .unwrap();
-        message = messages.first().unwrap().to_owned();
+    if let Some(word) = &react.spell {
+        match spell(word, &react.emotes) {
+            Ok(letters) => react.emotes.extend(letters),
+            Err(why) => return Response::Hidden(why),
+        }
+    }
+    // Reacting twice with the same emote does nothing
+    let mut emotes: Vec<ReactionType> = Vec::new();
+    for emote in react.emotes {
+        if !emotes.contains(&emote) {
+            emotes.push(emote);
+        }
+    }
+    if emotes.is_empty() {
+        return Response::Hidden("Please provide an emote or a word to spell".to_string());
+    }
+    if emotes.len() > MAX_REACTIONS {
+        return Response::Hidden(format!(
+            "A message can have at most {} reactions",
+            MAX_REACTIONS
+        ));
+    }
+    if react.targets.len() > 1 {
+        return Response::Hidden("Please pick only one way to find the message".to_string());
+    }
+    let target = react.targets.pop().unwrap_or(Target::Relative(1));
+    let message = match find_target(ctx, command.channel_id, command.user.id, target).await {
+        Ok(message) => message,
+        Err(why) => return Response::Hidden(why),
+    };
+    // React in order, one at a time
+    for emote in &emotes {
+        if let Err(why) = message.react(&ctx.http, emote.to_owned()).await {
+            println!("Unable to react with {}: {}", emote, why);
+            return Response::Hidden(format!("Unable to react with {}", emote));
+        }
+        if let ReactionType::Custom { id, .. } = emote {
+            record_use(&id.to_string(), command.user.id.0, Source::React).await;
+        }
     }
-    message
-        .react(&ctx.http, react.emote.to_owned())
-        .await
-        .unwrap();
     Response::Hidden("Successfully reacted".to_string())
     /*     let option = options
         .get(0)
@@ -169,14 +456,90 @@ pub async fn run(
     } */
 }
 
+/// How well a name matches what the user typed, higher is better
+fn match_score(name: &str, query: &str) -> Option<u32> {
+    let name = name.to_lowercase();
+    if query.is_empty() || name == query {
+        return Some(1000);
+    }
+    if name.starts_with(query) {
+        return Some(800);
+    }
+    if let Some(position) = name.find(query) {
+        return Some(600 - position.min(100) as u32);
+    }
+    // Every typed character appears in order, fewer gaps is better
+    let mut gaps = 0;
+    let mut chars = name.chars();
+    for c in query.chars() {
+        loop {
+            match chars.next() {
+                Some(n) if n == c => break,
+                Some(_) => gaps += 1,
+                None => return None,
+            }
+        }
+    }
+    Some(400 - gaps.min(300))
+}
+
+/// Emotes matching the query, best matches and most used first, capped at Discord's limit
+pub fn rank_emotes(
+    emotes: &[Emote],
+    usage: &HashMap<String, EmoteUsage>,
+    query: &str,
+) -> Vec<Emote> {
+    let query = query.to_lowercase();
+    let now = Utc::now().timestamp();
+    let mut ranked: Vec<(u32, f64, &Emote)> = emotes
+        .iter()
+        .filter_map(|emote| {
+            // Aliases match like the name, tags only count for less
+            let names = std::iter::once(&emote.name).chain(&emote.aliases);
+            let tags = emote.tags.iter();
+            let score = names
+                .filter_map(|name| match_score(name, &query))
+                .chain(tags.filter_map(|tag| Some(match_score(tag, &query)?.saturating_sub(200))))
+                .max()?;
+            let used = usage.get(&emote.emote).map_or(0.0, |u| u.score(now));
+            Some((score, used, emote))
+        })
+        .collect();
+    ranked.sort_by(|a, b| {
+        b.0.cmp(&a.0)
+            .then(b.1.total_cmp(&a.1))
+            .then(a.2.name.cmp(&b.2.name))
+    });
+    ranked
+        .into_iter()
+        .take(MAX_CHOICES)
+        .map(|(_, _, emote)| emote.clone())
+        .collect()
+}
+
 pub async fn send_autocomplete(autocomplete: &AutocompleteInteraction, ctx: &Context) {
-    // Open the emote file and read all emotes into a vector
-    let file = File::open("data/emotes.json").expect("Unable to open file");
-    let emotes: Vec<Emote> = serde_json::from_reader(file).expect("Unable to read file");
-    // Return a response with all the emotes
+    let typed = focused_value(&autocomplete.data.options);
+    // Only the last emote of a list is completed, the ones before it are kept
+    let (before, query) = match typed.rfind(',') {
+        Some(index) => (
+            format!("{}, ", typed[..index].trim_end()),
+            typed[index + 1..].trim(),
+        ),
+        None => (String::new(), typed.as_str()),
+    };
+    let emotes = rank_emotes(&EMOTES.read().await, &*USAGE.read().await, query);
+    // Return a response with the best matching emotes
     let autocomplete = autocomplete.create_autocomplete_response(&ctx.http, |response| {
         for emote in emotes {
-            response.add_string_choice(emote.name, emote.emote);
+            if before.is_empty() {
+                response.add_string_choice(emote.name, emote.emote);
+            } else {
+                let list = format!("{}{}", before, emote.name);
+                // Choices can't be longer than 100 characters
+                if list.chars().count() <= 100 {
+                    response.add_string_choice(&list, &list);
+                }
+            }
         }
         response
     });
@@ -185,34 +548,28 @@ pub async fn send_autocomplete(autocomplete: &AutocompleteInteraction, ctx: &Con
 }
 
 pub fn register(command: &mut CreateApplicationCommand) -> &mut CreateApplicationCommand {
-    // Create the required files if they don't exist
-    let file = File::open("data/emotes.json");
-    match file {
-        Ok(_) => {}
-        Err(_) => {
-            let emotes: Vec<Emote> = Vec::new();
-            let contents = serde_json::to_string_pretty(&emotes).unwrap();
-            File::create("data/emotes.json")
-                .unwrap()
-                .write_all(contents.as_bytes())
-                .unwrap();
-        }
-    };
     command
         .name("react")
-        .description("Get a user id")
+        .description("React to a message with emotes")
         .create_option(|option| {
             option
                 .name("emote")
-                .description("Choose an emote to react with")
+                .description("Emotes to react with, separated by commas")
                 .kind(CommandOptionType::String)
-                .required(true)
+                .required(false)
                 .set_autocomplete(true)
         })
         .create_option(|option| {
             option
-                .name("id")
-                .description("React to a message by id")
+                .name("spell")
+                .description("Word to spell with letter emojis")
+                .kind(CommandOptionType::String)
+                .required(false)
+        })
+        .create_option(|option| {
+            option
+                .name("message")
+                .description("Id or link of the message to react to")
                 .kind(CommandOptionType::String)
                 .required(false)
         })
@@ -220,9 +577,183 @@ pub fn register(command: &mut CreateApplicationCommand) -> &mut CreateApplicatio
             option
                 .name("relative")
                 .description("X messages ago to react to, defaults to last message in channel")
-                .kind(CommandOptionType::Number)
+                .kind(CommandOptionType::Integer)
                 .required(false)
-                .max_int_value(20)
+                .max_int_value(MAX_HISTORY)
                 .min_int_value(1)
         })
+        .create_option(|option| {
+            option
+                .name("user")
+                .description("React to the last message of this user")
+                .kind(CommandOptionType::User)
+                .required(false)
+        })
+        .create_option(|option| {
+            option
+                .name("reply")
+                .description("React to the message you last replied to")
+                .kind(CommandOptionType::Boolean)
+                .required(false)
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn unicode(emojis: &[&str]) -> Vec<ReactionType> {
+        emojis
+            .iter()
+            .map(|emoji| ReactionType::Unicode(emoji.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn parse_message_reads_ids() {
+        let here = ChannelId(1);
+        assert_eq!(parse_message("42", here), Some((here, MessageId(42))));
+        assert_eq!(parse_message(" 42 ", here), Some((here, MessageId(42))));
+        assert_eq!(parse_message("not an id", here), None);
+    }
+
+    #[test]
+    fn parse_message_reads_links() {
+        let here = ChannelId(1);
+        let expected = Some((ChannelId(20), MessageId(30)));
+        assert_eq!(
+            parse_message("https://discord.com/channels/10/20/30", here),
+            expected
+        );
+        assert_eq!(
+            parse_message("https://ptb.discord.com/channels/10/20/30/", here),
+            expected
+        );
+        // Links to a channel, or with a part that isn't an id
+        assert_eq!(
+            parse_message("https://discord.com/channels/10/20", here),
+            None
+        );
+        assert_eq!(
+            parse_message("https://discord.com/channels/10/x/30", here),
+            None
+        );
+    }
+
+    #[test]
+    fn nth_back_counts_from_the_latest() {
+        let newest_first = vec![3, 2, 1];
+        assert_eq!(nth_back(newest_first.clone(), 1), Some(3));
+        assert_eq!(nth_back(newest_first.clone(), 3), Some(1));
+        assert_eq!(nth_back(newest_first.clone(), 4), None);
+        assert_eq!(nth_back(newest_first, 0), None);
+    }
+
+    fn emote(name: &str, id: &str) -> Emote {
+        Emote {
+            name: name.to_string(),
+            emote: id.to_string(),
+            ..Default::default()
+        }
+    }
+
+    fn names(emotes: &[Emote]) -> Vec<&str> {
+        emotes.iter().map(|emote| emote.name.as_str()).collect()
+    }
+
+    #[test]
+    fn match_score_prefers_closer_matches() {
+        let exact = match_score("Pog", "pog").unwrap();
+        let prefix = match_score("pogchamp", "pog").unwrap();
+        let contains = match_score("superpog", "pog").unwrap();
+        let later = match_score("superduperpog", "pog").unwrap();
+        let scattered = match_score("pxoxg", "pog").unwrap();
+        assert!(exact > prefix);
+        assert!(prefix > contains);
+        assert!(contains > later);
+        assert!(later > scattered);
+        assert!(scattered > match_score("pxxoxxg", "pog").unwrap());
+        assert_eq!(match_score("kekw", "pog"), None);
+        assert_eq!(match_score("gop", "pog"), None);
+    }
+
+    #[test]
+    fn rank_emotes_orders_by_match_then_usage() {
+        let emotes = vec![
+            emote("superpog", "1"),
+            emote("pogchamp", "2"),
+            emote("pogu", "3"),
+            emote("pog", "4"),
+            emote("kekw", "5"),
+        ];
+        let mut usage = HashMap::new();
+        usage.insert(
+            "3".to_string(),
+            EmoteUsage {
+                count: 10,
+                last_used: Utc::now().timestamp(),
+                ..Default::default()
+            },
+        );
+        let ranked = rank_emotes(&emotes, &usage, "POG");
+        assert_eq!(names(&ranked), vec!["pog", "pogu", "pogchamp", "superpog"]);
+    }
+
+    #[test]
+    fn rank_emotes_matches_aliases_before_tags() {
+        let mut aliased = emote("happy", "1");
+        aliased.aliases.push("smile".to_string());
+        let mut tagged = emote("grin", "2");
+        tagged.tags.push("smile".to_string());
+        let ranked = rank_emotes(&[tagged, aliased], &HashMap::new(), "smile");
+        assert_eq!(names(&ranked), vec!["happy", "grin"]);
+    }
+
+    #[test]
+    fn rank_emotes_is_capped() {
+        let emotes: Vec<Emote> = (0..40)
+            .map(|i| emote(&format!("emote{}", i), &i.to_string()))
+            .collect();
+        let ranked = rank_emotes(&emotes, &HashMap::new(), "");
+        assert_eq!(ranked.len(), MAX_CHOICES);
+        // Ties are broken by name
+        assert_eq!(ranked[0].name, "emote0");
+        assert_eq!(ranked[1].name, "emote1");
+        assert_eq!(ranked[2].name, "emote10");
+    }
+
+    #[test]
+    fn keycaps_are_emojis() {
+        assert!(is_keycap("1\u{fe0f}\u{20e3}"));
+        assert!(is_keycap("#\u{fe0f}\u{20e3}"));
+        assert!(is_keycap("*\u{20e3}"));
+        assert!(!is_keycap("1"));
+        assert!(!is_keycap("a\u{fe0f}\u{20e3}"));
+        assert!(!is_keycap("12\u{fe0f}\u{20e3}"));
+    }
+
+    #[test]
+    fn letter_emojis_start_with_the_indicator() {
+        assert_eq!(letter_emojis('A'), vec!["🇦", "🅰️"]);
+        assert_eq!(letter_emojis('o'), vec!["🇴", "🅾️", "⭕"]);
+        assert_eq!(letter_emojis('7'), vec!["7\u{fe0f}\u{20e3}"]);
+        assert_eq!(letter_emojis('?'), vec!["❓", "❔"]);
+        assert!(letter_emojis('-').is_empty());
+    }
+
+    #[test]
+    fn spell_uses_fallbacks_for_repeated_letters() {
+        assert_eq!(spell("Hi", &[]), Ok(unicode(&["🇭", "🇮"])));
+        assert_eq!(spell("a b a", &[]), Ok(unicode(&["🇦", "🇧", "🅰️"])));
+        assert_eq!(spell("oo", &unicode(&["🇴"])), Ok(unicode(&["🅾️", "⭕"])));
+    }
+
+    #[test]
+    fn spell_fails_when_letters_run_out() {
+        assert_eq!(
+            spell("hh", &[]),
+            Err("'h' is used too often to spell".to_string())
+        );
+        assert_eq!(spell("a-b", &[]), Err("Unable to spell '-'".to_string()));
+    }
 }
@@ -0,0 +1,76 @@
+use serenity::builder::CreateApplicationCommand;
+use serenity::model::application::command::CommandType;
+use serenity::model::prelude::interaction::application_command::{
+    ApplicationCommandInteraction, ResolvedTarget,
+};
+use serenity::model::prelude::interaction::message_component::MessageComponentInteraction;
+use serenity::model::prelude::{ChannelId, ReactionType};
+use serenity::prelude::Context;
+
+use crate::commands::handler::{Response, SelectChoice};
+use crate::commands::react::{rank_emotes, resolve_emote, EMOTES};
+use crate::commands::usage::{record_use, Source, USAGE};
+
+/// Shows the most used emotes to pick from for the message that was right clicked
+pub async fn run(command: &ApplicationCommandInteraction) -> Response {
+    let message = match command.data.target() {
+        Some(ResolvedTarget::Message(message)) => message,
+        _ => return Response::Hidden("Please pick a message".to_string()),
+    };
+    let emotes = rank_emotes(&EMOTES.read().await, &*USAGE.read().await, "");
+    if emotes.is_empty() {
+        return Response::Hidden("There are no emotes yet".to_string());
+    }
+    let choices = emotes
+        .into_iter()
+        .map(|emote| SelectChoice {
+            emoji: ReactionType::try_from(emote.mention()).ok(),
+            label: emote.name,
+            value: emote.emote,
+        })
+        .collect();
+    Response::Select(
+        "Pick the emotes to react with, use /react for the others".to_string(),
+        format!("reactwith:{}:{}", message.channel_id, message.id),
+        choices,
+    )
+}
+
+/// Reacts with the picked emotes to the message of the prompt
+pub async fn select(
+    data: &str,
+    ctx: &Context,
+    component: &MessageComponentInteraction,
+) -> Response {
+    let ids = data
+        .split_once(':')
+        .and_then(|(channel, message)| Some((channel.parse().ok()?, message.parse().ok()?)));
+    let (channel, message): (u64, u64) = match ids {
+        Some(ids) => ids,
+        None => return Response::Hidden("Invalid message".to_string()),
+    };
+    let message = match ChannelId(channel).message(&ctx.http, message).await {
+        Ok(message) => message,
+        Err(_) => return Response::Hidden("Unable to find the message".to_string()),
+    };
+    let mut reacted = Vec::new();
+    for value in &component.data.values {
+        let emote = match resolve_emote(ctx, value).await {
+            Ok(emote) => emote,
+            Err(why) => return Response::Hidden(why),
+        };
+        if let Err(why) = message.react(&ctx.http, emote.clone()).await {
+            println!("Unable to react with {}: {}", emote, why);
+            return Response::Hidden(format!("Unable to react with {}", emote));
+        }
+        if let ReactionType::Custom { id, .. } = &emote {
+            record_use(&id.to_string(), component.user.id.0, Source::React).await;
+        }
+        reacted.push(emote.to_string());
+    }
+    Response::Hidden(format!("Reacted with {}", reacted.join(" ")))
+}
+
+pub fn register(command: &mut CreateApplicationCommand) -> &mut CreateApplicationCommand {
+    command.name("React with emote").kind(CommandType::Message)
+}
@@ -0,0 +1,566 @@
+use std::sync::atomic::Ordering;
+
+use lazy_static::lazy_static;
+use serde::{Deserialize, Serialize};
+use serenity::builder::{CreateApplicationCommand, CreateComponents};
+use serenity::model::prelude::command::CommandOptionType;
+use serenity::model::prelude::component::ButtonStyle;
+use serenity::model::prelude::interaction::application_command::{
+    ApplicationCommandInteraction, CommandDataOption, CommandDataOptionValue,
+};
+use serenity::model::prelude::interaction::message_component::MessageComponentInteraction;
+use serenity::model::prelude::{ChannelId, MessageId, Reaction, ReactionType, RoleId};
+use serenity::prelude::Context;
+
+use crate::commands::handler::{is_admin, Response};
+use crate::commands::react::{parse_message, reactors, resolve_emote, same_emoji};
+use crate::commands::usage::BOT_ID;
+use crate::store::Store;
+
+// Discord allows 5 rows of 5 buttons on a message
+const MAX_BUTTONS: usize = 25;
+
+lazy_static! {
+    pub static ref PANELS: Store<Vec<Panel>> = Store::load("data/reaction_roles.json");
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Panel {
+    pub guild_id: u64,
+    pub channel_id: u64,
+    pub message_id: u64,
+    pub bindings: Vec<Binding>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Binding {
+    pub role: u64,
+    /// Name of the role, used as the button label
+    pub label: String,
+    /// Reaction or button emoji, in message format
+    pub emote: Option<String>,
+    pub button: bool,
+    /// Users that got the role by reacting, so it can be taken away
+    /// from users who removed their reaction while the bot was offline
+    #[serde(default)]
+    pub members: Vec<u64>,
+}
+
+impl Binding {
+    fn reaction(&self) -> Option<ReactionType> {
+        self.emote
+            .as_deref()
+            .and_then(|emote| ReactionType::try_from(emote).ok())
+    }
+}
+
+fn panel_buttons<'a>(
+    components: &'a mut CreateComponents,
+    panel: &Panel,
+) -> &'a mut CreateComponents {
+    let buttons: Vec<&Binding> = panel.bindings.iter().filter(|b| b.button).collect();
+    for row in buttons.chunks(5) {
+        components.create_action_row(|action_row| {
+            for binding in row {
+                action_row.create_button(|button| {
+                    button
+                        .label(&binding.label)
+                        .style(ButtonStyle::Primary)
+                        .custom_id(format!("reactionrole:{}", binding.role));
+                    if let Some(emote) = binding.reaction() {
+                        button.emoji(emote);
+                    }
+                    button
+                });
+            }
+            action_row
+        });
+    }
+    components
+}
+
+/// Updates the buttons of the panel message to match its bindings
+async fn render_panel(ctx: &Context, panel: &Panel) -> Result<(), String> {
+    ChannelId(panel.channel_id)
+        .edit_message(&ctx.http, panel.message_id, |message| {
+            message.components(|components| panel_buttons(components, panel))
+        })
+        .await
+        .map(|_| ())
+        .map_err(|why| {
+            println!("Unable to update reaction role panel: {}", why);
+            "Unable to update the panel message".to_string()
+        })
+}
+
+async fn set_role(ctx: &Context, guild: u64, user: u64, role: u64, add: bool) -> bool {
+    let result = if add {
+        ctx.http
+            .add_member_role(guild, user, role, Some("Reaction role"))
+            .await
+    } else {
+        ctx.http
+            .remove_member_role(guild, user, role, Some("Reaction role"))
+            .await
+    };
+    if let Err(why) = &result {
+        println!("Unable to update role {} of {}: {}", role, user, why);
+    }
+    result.is_ok()
+}
+
+/// Gives or takes the role bound to a reaction on a panel
+pub async fn reaction_changed(ctx: &Context, reaction: &Reaction, added: bool) {
+    let user = match reaction.user_id {
+        Some(user) if user.0 != BOT_ID.load(Ordering::Relaxed) => user.0,
+        _ => return,
+    };
+    let panel = PANELS
+        .read()
+        .await
+        .iter()
+        .find(|panel| panel.message_id == reaction.message_id.0)
+        .cloned();
+    let panel = match panel {
+        Some(panel) => panel,
+        None => return,
+    };
+    let binding = panel.bindings.iter().find(|binding| {
+        !binding.button
+            && binding
+                .reaction()
+                .is_some_and(|emote| same_emoji(&emote, &reaction.emoji))
+    });
+    let role = match binding {
+        Some(binding) => binding.role,
+        None => return,
+    };
+    if !set_role(ctx, panel.guild_id, user, role, added).await {
+        return;
+    }
+    PANELS
+        .update(|panels| {
+            let binding = panels
+                .iter_mut()
+                .find(|p| p.message_id == panel.message_id)
+                .and_then(|p| p.bindings.iter_mut().find(|b| b.role == role));
+            if let Some(binding) = binding {
+                binding.members.retain(|&member| member != user);
+                if added {
+                    binding.members.push(user);
+                }
+            }
+        })
+        .await;
+}
+
+/// Catches up on reactions added or removed while the bot was offline
+pub async fn sync_reaction_roles(ctx: &Context) {
+    let panels = PANELS.read().await.clone();
+    for panel in panels {
+        for binding in panel.bindings.iter().filter(|binding| !binding.button) {
+            let emote = match binding.reaction() {
+                Some(emote) => emote,
+                None => continue,
+            };
+            let users =
+                match reactors(ctx, ChannelId(panel.channel_id), panel.message_id, &emote).await {
+                    Ok(users) => users,
+                    Err(why) => {
+                        println!(
+                            "Unable to get reactions of panel {}: {}",
+                            panel.message_id, why
+                        );
+                        continue;
+                    }
+                };
+            let mut members = Vec::new();
+            for &user in &users {
+                if binding.members.contains(&user)
+                    || set_role(ctx, panel.guild_id, user, binding.role, true).await
+                {
+                    members.push(user);
+                }
+            }
+            for &user in binding.members.iter().filter(|user| !users.contains(user)) {
+                // Keep tracking users whose role couldn't be removed, to retry next time
+                if !set_role(ctx, panel.guild_id, user, binding.role, false).await {
+                    members.push(user);
+                }
+            }
+            PANELS
+                .update(|panels| {
+                    let binding = panels
+                        .iter_mut()
+                        .find(|p| p.message_id == panel.message_id)
+                        .and_then(|p| p.bindings.iter_mut().find(|b| b.role == binding.role));
+                    if let Some(binding) = binding {
+                        binding.members = members;
+                    }
+                })
+                .await;
+        }
+    }
+}
+
+/// Toggles the role of a panel button
+pub async fn toggle(
+    data: &str,
+    ctx: &Context,
+    component: &MessageComponentInteraction,
+) -> Response {
+    let role = match data.parse::<u64>() {
+        Ok(role) => role,
+        Err(_) => return Response::Hidden("Invalid role".to_string()),
+    };
+    let (guild, member) = match (component.guild_id, component.member.as_ref()) {
+        (Some(guild), Some(member)) => (guild, member),
+        _ => return Response::Hidden("Roles can only be picked in a server".to_string()),
+    };
+    let bound = PANELS.read().await.iter().any(|panel| {
+        panel.message_id == component.message.id.0
+            && panel.bindings.iter().any(|b| b.button && b.role == role)
+    });
+    if !bound {
+        return Response::Hidden("That role is no longer on this panel".to_string());
+    }
+    let add = !member.roles.contains(&RoleId(role));
+    if !set_role(ctx, guild.0, component.user.id.0, role, add).await {
+        return Response::Hidden("Unable to update your roles".to_string());
+    }
+    if add {
+        Response::Hidden(format!("Gave you <@&{}>", role))
+    } else {
+        Response::Hidden(format!("Took away <@&{}>", role))
+    }
+}
+
+pub async fn run(
+    options: &[CommandDataOption],
+    ctx: &Context,
+    command: &ApplicationCommandInteraction,
+) -> Response {
+    if !is_admin(command.user.id.0) {
+        return Response::Hidden("You are not an admin".to_string());
+    }
+    let subcommand = match options.first() {
+        Some(subcommand) => subcommand,
+        None => return Response::Hidden("Please provide a subcommand".to_string()),
+    };
+    match subcommand.name.as_str() {
+        "create" => create(&subcommand.options, ctx, command).await,
+        "bind" => bind(&subcommand.options, ctx, command).await,
+        "unbind" => unbind(&subcommand.options, ctx, command).await,
+        "delete" => delete(&subcommand.options, ctx, command).await,
+        _ => Response::Hidden("Unknown subcommand".to_string()),
+    }
+}
+
+async fn create(
+    options: &[CommandDataOption],
+    ctx: &Context,
+    command: &ApplicationCommandInteraction,
+) -> Response {
+    let guild_id = match command.guild_id {
+        Some(guild_id) => guild_id,
+        None => return Response::Hidden("Panels can only be created in a server".to_string()),
+    };
+    let mut text = None;
+    let mut channel = command.channel_id;
+    for option in options {
+        match option.resolved.as_ref() {
+            Some(CommandDataOptionValue::String(value)) => text = Some(value.to_string()),
+            Some(CommandDataOptionValue::Channel(value)) => channel = value.id,
+            _ => {}
+        }
+    }
+    let text = text.unwrap_or_else(|| "React to pick your roles".to_string());
+    let message = match channel.send_message(&ctx.http, |m| m.content(text)).await {
+        Ok(message) => message,
+        Err(why) => {
+            println!("Unable to create reaction role panel: {}", why);
+            return Response::Hidden("Unable to send the panel in that channel".to_string());
+        }
+    };
+    PANELS
+        .update(|panels| {
+            panels.push(Panel {
+                guild_id: guild_id.0,
+                channel_id: channel.0,
+                message_id: message.id.0,
+                bindings: Vec::new(),
+            })
+        })
+        .await;
+    Response::Hidden(format!(
+        "Created panel {}, add roles with /reactionroles bind",
+        message.id
+    ))
+}
+
+/// The panel a message id or link option points at
+async fn option_panel(
+    options: &[CommandDataOption],
+    channel: ChannelId,
+) -> Result<Panel, Response> {
+    let message = options
+        .iter()
+        .find(|option| option.name == "panel")
+        .and_then(|option| match option.resolved.as_ref() {
+            Some(CommandDataOptionValue::String(value)) => parse_message(value, channel),
+            _ => None,
+        });
+    let message: MessageId = match message {
+        Some((_, message)) => message,
+        None => {
+            return Err(Response::Hidden(
+                "Please provide a panel id or link".to_string(),
+            ))
+        }
+    };
+    PANELS
+        .read()
+        .await
+        .iter()
+        .find(|panel| panel.message_id == message.0)
+        .cloned()
+        .ok_or_else(|| Response::Hidden("That message is not a reaction role panel".to_string()))
+}
+
+async fn bind(
+    options: &[CommandDataOption],
+    ctx: &Context,
+    command: &ApplicationCommandInteraction,
+) -> Response {
+    let mut panel = match option_panel(options, command.channel_id).await {
+        Ok(panel) => panel,
+        Err(response) => return response,
+    };
+    let mut role = None;
+    let mut emote = None;
+    let mut button = false;
+    for option in options {
+        match (option.name.as_str(), option.resolved.as_ref()) {
+            ("role", Some(CommandDataOptionValue::Role(value))) => role = Some(value.clone()),
+            ("emote", Some(CommandDataOptionValue::String(value))) => {
+                match resolve_emote(ctx, value).await {
+                    Ok(value) => emote = Some(value),
+                    Err(why) => return Response::Hidden(why),
+                }
+            }
+            ("button", Some(CommandDataOptionValue::Boolean(value))) => button = *value,
+            _ => {}
+        }
+    }
+    let role = match role {
+        Some(role) => role,
+        None => return Response::Hidden("Please provide a role".to_string()),
+    };
+    if panel
+        .bindings
+        .iter()
+        .any(|binding| binding.role == role.id.0)
+    {
+        return Response::Hidden("That role is already on the panel".to_string());
+    }
+    if !button {
+        let emote = match &emote {
+            Some(emote) => emote,
+            None => return Response::Hidden("Reactions need an emote".to_string()),
+        };
+        let taken = panel.bindings.iter().any(|binding| {
+            !binding.button && binding.reaction().is_some_and(|e| same_emoji(&e, emote))
+        });
+        if taken {
+            return Response::Hidden("That emote is already used on the panel".to_string());
+        }
+        let reacted = ChannelId(panel.channel_id)
+            .create_reaction(&ctx.http, panel.message_id, emote.clone())
+            .await;
+        if let Err(why) = reacted {
+            println!("Unable to react to panel: {}", why);
+            return Response::Hidden(format!("Unable to react with {}", emote));
+        }
+    } else if panel.bindings.iter().filter(|b| b.button).count() >= MAX_BUTTONS {
+        return Response::Hidden(format!("Panels can have at most {} buttons", MAX_BUTTONS));
+    }
+    panel.bindings.push(Binding {
+        role: role.id.0,
+        label: role.name.clone(),
+        emote: emote.map(|emote| emote.to_string()),
+        button,
+        members: Vec::new(),
+    });
+    if button {
+        if let Err(why) = render_panel(ctx, &panel).await {
+            return Response::Hidden(why);
+        }
+    }
+    let bindings = panel.bindings.clone();
+    PANELS
+        .update(|panels| {
+            if let Some(p) = panels.iter_mut().find(|p| p.message_id == panel.message_id) {
+                p.bindings = bindings;
+            }
+        })
+        .await;
+    Response::Hidden(format!("Bound <@&{}> to the panel", role.id))
+}
+
+async fn unbind(
+    options: &[CommandDataOption],
+    ctx: &Context,
+    command: &ApplicationCommandInteraction,
+) -> Response {
+    let mut panel = match option_panel(options, command.channel_id).await {
+        Ok(panel) => panel,
+        Err(response) => return response,
+    };
+    let role = options
+        .iter()
+        .find_map(|option| match option.resolved.as_ref() {
+            Some(CommandDataOptionValue::Role(role)) => Some(role.id.0),
+            _ => None,
+        });
+    let index = match panel.bindings.iter().position(|b| Some(b.role) == role) {
+        Some(index) => index,
+        None => return Response::Hidden("That role is not on the panel".to_string()),
+    };
+    let binding = panel.bindings.remove(index);
+    if binding.button {
+        if let Err(why) = render_panel(ctx, &panel).await {
+            return Response::Hidden(why);
+        }
+    } else if let Some(emote) = binding.reaction() {
+        // Only the bot's own reaction is removed, users keep the role until they unreact
+        if let Err(why) = ChannelId(panel.channel_id)
+            .delete_reaction(&ctx.http, panel.message_id, None, emote)
+            .await
+        {
+            println!("Unable to remove panel reaction: {}", why);
+        }
+    }
+    PANELS
+        .update(|panels| {
+            if let Some(p) = panels.iter_mut().find(|p| p.message_id == panel.message_id) {
+                p.bindings.retain(|b| b.role != binding.role);
+            }
+        })
+        .await;
+    Response::Hidden(format!("Removed <@&{}> from the panel", binding.role))
+}
+
+async fn delete(
+    options: &[CommandDataOption],
+    ctx: &Context,
+    command: &ApplicationCommandInteraction,
+) -> Response {
+    let panel = match option_panel(options, command.channel_id).await {
+        Ok(panel) => panel,
+        Err(response) => return response,
+    };
+    if let Err(why) = ChannelId(panel.channel_id)
+        .delete_message(&ctx.http, panel.message_id)
+        .await
+    {
+        println!("Unable to delete panel message: {}", why);
+    }
+    PANELS
+        .update(|panels| panels.retain(|p| p.message_id != panel.message_id))
+        .await;
+    Response::Hidden("Deleted the panel".to_string())
+}
+
+pub fn register(command: &mut CreateApplicationCommand) -> &mut CreateApplicationCommand {
+    command
+        .name("reactionroles")
+        .description("Panels that give roles for reactions or buttons")
+        .create_option(|option| {
+            option
+                .name("create")
+                .description("Send a new panel")
+                .kind(CommandOptionType::SubCommand)
+                .create_sub_option(|option| {
+                    option
+                        .name("text")
+                        .description("Text of the panel")
+                        .kind(CommandOptionType::String)
+                        .required(false)
+                })
+                .create_sub_option(|option| {
+                    option
+                        .name("channel")
+                        .description("Channel to send the panel in, defaults to this one")
+                        .kind(CommandOptionType::Channel)
+                        .required(false)
+                })
+        })
+        .create_option(|option| {
+            option
+                .name("bind")
+                .description("Give a role for a reaction or button on a panel")
+                .kind(CommandOptionType::SubCommand)
+                .create_sub_option(|option| {
+                    option
+                        .name("panel")
+                        .description("Id or link of the panel message")
+                        .kind(CommandOptionType::String)
+                        .required(true)
+                })
+                .create_sub_option(|option| {
+                    option
+                        .name("role")
+                        .description("Role to give")
+                        .kind(CommandOptionType::Role)
+                        .required(true)
+                })
+                .create_sub_option(|option| {
+                    option
+                        .name("emote")
+                        .description("Emote to react with, optional for buttons")
+                        .kind(CommandOptionType::String)
+                        .required(false)
+                        .set_autocomplete(true)
+                })
+                .create_sub_option(|option| {
+                    option
+                        .name("button")
+                        .description("Use a button instead of a reaction")
+                        .kind(CommandOptionType::Boolean)
+                        .required(false)
+                })
+        })
+        .create_option(|option| {
+            option
+                .name("unbind")
+                .description("Remove a role from a panel")
+                .kind(CommandOptionType::SubCommand)
+                .create_sub_option(|option| {
+                    option
+                        .name("panel")
+                        .description("Id or link of the panel message")
+                        .kind(CommandOptionType::String)
+                        .required(true)
+                })
+                .create_sub_option(|option| {
+                    option
+                        .name("role")
+                        .description("Role to remove")
+                        .kind(CommandOptionType::Role)
+                        .required(true)
+                })
+        })
+        .create_option(|option| {
+            option
+                .name("delete")
+                .description("Delete a panel and its message")
+                .kind(CommandOptionType::SubCommand)
+                .create_sub_option(|option| {
+                    option
+                        .name("panel")
+                        .description("Id or link of the panel message")
+                        .kind(CommandOptionType::String)
+                        .required(true)
+                })
+        })
+}
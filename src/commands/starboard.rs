@@ -0,0 +1,286 @@
+use std::collections::HashMap;
+
+use lazy_static::lazy_static;
+use serde::{Deserialize, Serialize};
+use serenity::builder::CreateApplicationCommand;
+use serenity::model::prelude::command::CommandOptionType;
+use serenity::model::prelude::interaction::application_command::{
+    ApplicationCommandInteraction, CommandDataOption, CommandDataOptionValue,
+};
+use serenity::model::prelude::{ChannelId, Message, Reaction, ReactionType};
+use serenity::prelude::Context;
+use tokio::sync::Mutex;
+
+use crate::commands::handler::{is_admin, Response};
+use crate::commands::react::{reactors, resolve_emote, same_emoji};
+use crate::store::Store;
+
+const DEFAULT_EMOTE: &str = "⭐";
+const DEFAULT_THRESHOLD: u64 = 3;
+
+lazy_static! {
+    pub static ref STARBOARD: Store<Starboard> = Store::load("data/starboard.json");
+    /// Reactions come in quickly, this keeps two of them from posting the same message
+    static ref STARBOARD_LOCK: Mutex<()> = Mutex::new(());
+}
+
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct Starboard {
+    /// Settings of each guild, keyed by guild id
+    pub guilds: HashMap<u64, StarboardConfig>,
+    /// Reposted messages, keyed by the id of the original message
+    pub posts: HashMap<u64, StarPost>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct StarboardConfig {
+    pub channel: u64,
+    pub threshold: u64,
+    /// Emote counted as a star, in message format
+    pub emote: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct StarPost {
+    pub channel: u64,
+    /// Id of the repost in the starboard channel
+    pub post: u64,
+}
+
+fn post_content(emote: &str, count: u64, channel: ChannelId) -> String {
+    format!("{} **{}** | <#{}>", emote, count, channel)
+}
+
+/// Reposts the message in the starboard channel as an embed with a jump link
+async fn create_post(
+    ctx: &Context,
+    config: &StarboardConfig,
+    message: &Message,
+    count: u64,
+) -> Result<Message, String> {
+    let image = message
+        .attachments
+        .iter()
+        .find(|attachment| {
+            attachment
+                .content_type
+                .as_deref()
+                .is_some_and(|kind| kind.starts_with("image/"))
+        })
+        .map(|attachment| attachment.url.clone());
+    ChannelId(config.channel)
+        .send_message(&ctx.http, |m| {
+            m.content(post_content(&config.emote, count, message.channel_id))
+                .embed(|embed| {
+                    embed
+                        .author(|author| {
+                            author
+                                .name(&message.author.name)
+                                .icon_url(message.author.face())
+                        })
+                        .description(&message.content)
+                        .field(
+                            "Source",
+                            format!("[Jump to message]({})", message.link()),
+                            false,
+                        )
+                        .timestamp(message.timestamp);
+                    if let Some(image) = &image {
+                        embed.image(image);
+                    }
+                    embed
+                })
+        })
+        .await
+        .map_err(|why| why.to_string())
+}
+
+/// Updates the starboard after a reaction was added or removed
+pub async fn reaction_changed(ctx: &Context, reaction: &Reaction) {
+    let guild = match reaction.guild_id {
+        Some(guild) => guild,
+        None => return,
+    };
+    let config = match STARBOARD.read().await.guilds.get(&guild.0) {
+        Some(config) => config.clone(),
+        None => return,
+    };
+    let star = match ReactionType::try_from(config.emote.as_str()) {
+        Ok(star) => star,
+        Err(_) => return,
+    };
+    if !same_emoji(&star, &reaction.emoji) || reaction.channel_id.0 == config.channel {
+        return;
+    }
+    // Messages from NSFW channels are never reposted
+    let channel = reaction.channel_id.to_channel(&ctx.http).await;
+    if channel.map_or(true, |channel| channel.is_nsfw()) {
+        return;
+    }
+    let _lock = STARBOARD_LOCK.lock().await;
+    let message = match reaction.message(&ctx.http).await {
+        Ok(message) => message,
+        Err(why) => {
+            println!("Unable to get starred message: {}", why);
+            return;
+        }
+    };
+    let users = match reactors(ctx, message.channel_id, message.id.0, &star).await {
+        Ok(users) => users,
+        Err(why) => {
+            println!("Unable to get stars: {}", why);
+            return;
+        }
+    };
+    // Starring your own message doesn't count
+    let count = users
+        .iter()
+        .filter(|&&user| user != message.author.id.0)
+        .count() as u64;
+    let post = STARBOARD.read().await.posts.get(&message.id.0).cloned();
+    match post {
+        Some(post) if count >= config.threshold => {
+            let content = post_content(&config.emote, count, message.channel_id);
+            if let Err(why) = ChannelId(post.channel)
+                .edit_message(&ctx.http, post.post, |m| m.content(content))
+                .await
+            {
+                println!("Unable to update starboard post: {}", why);
+            }
+        }
+        Some(post) => {
+            if let Err(why) = ChannelId(post.channel)
+                .delete_message(&ctx.http, post.post)
+                .await
+            {
+                println!("Unable to delete starboard post: {}", why);
+            }
+            STARBOARD
+                .update(|starboard| starboard.posts.remove(&message.id.0))
+                .await;
+        }
+        None if count >= config.threshold => match create_post(ctx, &config, &message, count).await
+        {
+            Ok(post) => {
+                STARBOARD
+                    .update(|starboard| {
+                        starboard.posts.insert(
+                            message.id.0,
+                            StarPost {
+                                channel: config.channel,
+                                post: post.id.0,
+                            },
+                        )
+                    })
+                    .await;
+            }
+            Err(why) => println!("Unable to post to the starboard: {}", why),
+        },
+        None => {}
+    }
+}
+
+pub async fn run(
+    options: &[CommandDataOption],
+    ctx: &Context,
+    command: &ApplicationCommandInteraction,
+) -> Response {
+    if !is_admin(command.user.id.0) {
+        return Response::Hidden("You are not an admin".to_string());
+    }
+    let guild = match command.guild_id {
+        Some(guild) => guild,
+        None => {
+            return Response::Hidden("The starboard can only be set up in a server".to_string())
+        }
+    };
+    let subcommand = match options.first() {
+        Some(subcommand) => subcommand,
+        None => return Response::Hidden("Please provide a subcommand".to_string()),
+    };
+    match subcommand.name.as_str() {
+        "setup" => {
+            let mut config = StarboardConfig {
+                channel: command.channel_id.0,
+                threshold: DEFAULT_THRESHOLD,
+                emote: DEFAULT_EMOTE.to_string(),
+            };
+            for option in &subcommand.options {
+                match (option.name.as_str(), option.resolved.as_ref()) {
+                    ("channel", Some(CommandDataOptionValue::Channel(channel))) => {
+                        config.channel = channel.id.0
+                    }
+                    ("threshold", Some(CommandDataOptionValue::Integer(threshold))) => {
+                        config.threshold = (*threshold).max(1) as u64
+                    }
+                    ("emote", Some(CommandDataOptionValue::String(emote))) => {
+                        match resolve_emote(ctx, emote).await {
+                            Ok(emote) => config.emote = emote.to_string(),
+                            Err(why) => return Response::Hidden(why),
+                        }
+                    }
+                    _ => {}
+                }
+            }
+            let content = format!(
+                "Messages with {} {} are reposted in <#{}>",
+                config.threshold, config.emote, config.channel
+            );
+            STARBOARD
+                .update(|starboard| starboard.guilds.insert(guild.0, config))
+                .await;
+            Response::Shown(content)
+        }
+        "disable" => {
+            let removed = STARBOARD
+                .update(|starboard| starboard.guilds.remove(&guild.0))
+                .await;
+            match removed {
+                Some(_) => Response::Shown("Disabled the starboard".to_string()),
+                None => Response::Hidden("The starboard isn't set up".to_string()),
+            }
+        }
+        _ => Response::Hidden("Unknown subcommand".to_string()),
+    }
+}
+
+pub fn register(command: &mut CreateApplicationCommand) -> &mut CreateApplicationCommand {
+    command
+        .name("starboard")
+        .description("Repost messages with many stars")
+        .create_option(|option| {
+            option
+                .name("setup")
+                .description("Set up or change the starboard of this server")
+                .kind(CommandOptionType::SubCommand)
+                .create_sub_option(|option| {
+                    option
+                        .name("channel")
+                        .description("Channel to repost in, defaults to this one")
+                        .kind(CommandOptionType::Channel)
+                        .required(false)
+                })
+                .create_sub_option(|option| {
+                    option
+                        .name("threshold")
+                        .description("Stars needed, defaults to 3")
+                        .kind(CommandOptionType::Integer)
+                        .min_int_value(1)
+                        .required(false)
+                })
+                .create_sub_option(|option| {
+                    option
+                        .name("emote")
+                        .description("Emote counted as a star, defaults to ⭐")
+                        .kind(CommandOptionType::String)
+                        .required(false)
+                        .set_autocomplete(true)
+                })
+        })
+        .create_option(|option| {
+            option
+                .name("disable")
+                .description("Stop reposting messages")
+                .kind(CommandOptionType::SubCommand)
+        })
+}
@@ -0,0 +1,294 @@
+use lazy_static::lazy_static;
+use serde::{Deserialize, Serialize};
+use serenity::builder::CreateApplicationCommand;
+use serenity::model::prelude::command::CommandOptionType;
+use serenity::model::prelude::interaction::application_command::{
+    ApplicationCommandInteraction, CommandDataOption, CommandDataOptionValue,
+};
+use serenity::model::prelude::interaction::autocomplete::AutocompleteInteraction;
+use serenity::model::prelude::{
+    AttachmentType, GuildId, ReactionType, Sticker, StickerFormatType, StickerId,
+};
+use serenity::prelude::Context;
+
+use crate::commands::emotes::string_option;
+use crate::commands::handler::{focused_value, is_admin, Response};
+use crate::commands::react::resolve_emote;
+use crate::imaging::sticker::prepare_sticker;
+use crate::imaging::{download_image, process};
+use crate::store::Store;
+
+lazy_static! {
+    pub static ref STICKERS: Store<Vec<StickerEntry>> = Store::load("data/stickers.json");
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct StickerEntry {
+    pub name: String,
+    pub id: u64,
+    pub guild: u64,
+    /// Standard emoji the sticker is related to
+    pub emoji: String,
+    pub animated: bool,
+}
+
+impl StickerEntry {
+    fn from_sticker(sticker: &Sticker, guild: GuildId) -> StickerEntry {
+        StickerEntry {
+            name: sticker.name.clone(),
+            id: sticker.id.0,
+            guild: guild.0,
+            emoji: sticker.tags.first().cloned().unwrap_or_default(),
+            animated: sticker.format_type == StickerFormatType::Apng,
+        }
+    }
+}
+
+/// Replaces the registry stickers of the guild with the ones it has now
+pub async fn reconcile_guild(guild: GuildId, stickers: Vec<Sticker>) {
+    STICKERS
+        .update(|entries| {
+            entries.retain(|entry| entry.guild != guild.0);
+            entries.extend(
+                stickers
+                    .iter()
+                    .map(|sticker| StickerEntry::from_sticker(sticker, guild)),
+            );
+        })
+        .await;
+}
+
+/// Reconciles the stickers of every guild the bot is in, to catch up on changes made while offline.
+/// Guilds that can't be fetched are left alone so a failed request doesn't empty the registry.
+pub async fn reconcile(ctx: &Context, guilds: &[GuildId]) {
+    for guild in guilds {
+        match guild.stickers(&ctx.http).await {
+            Ok(stickers) => reconcile_guild(*guild, stickers).await,
+            Err(why) => println!("Unable to get stickers of guild {}: {}", guild, why),
+        }
+    }
+}
+
+async fn create(
+    options: &[CommandDataOption],
+    ctx: &Context,
+    command: &ApplicationCommandInteraction,
+    guild: GuildId,
+) -> Response {
+    if !is_admin(command.user.id.0) {
+        return Response::Hidden("You are not an admin".to_string());
+    }
+    // Discord checks the lengths of the name and description
+    let name = string_option(options, "name").unwrap_or_default();
+    let description = string_option(options, "description").unwrap_or_default();
+    // Discord only relates stickers to standard emojis
+    let emoji = match resolve_emote(ctx, string_option(options, "emoji").unwrap_or_default()).await
+    {
+        Ok(ReactionType::Unicode(emoji)) => emoji,
+        _ => {
+            return Response::Hidden(
+                "Please provide a standard emoji, not a custom one".to_string(),
+            )
+        }
+    };
+    let attachment = options
+        .iter()
+        .find(|option| option.name == "image")
+        .and_then(|option| match option.resolved.as_ref() {
+            Some(CommandDataOptionValue::Attachment(attachment)) => Some(attachment),
+            _ => None,
+        });
+    let attachment = match attachment {
+        Some(attachment) => attachment,
+        None => return Response::Hidden("Please provide a valid image".to_string()),
+    };
+    if STICKERS
+        .read()
+        .await
+        .iter()
+        .any(|entry| entry.guild == guild.0 && entry.name.eq_ignore_ascii_case(name))
+    {
+        return Response::Hidden("A sticker by that name already exists".to_string());
+    }
+    let bytes = match download_image(&attachment.url).await {
+        Ok(bytes) => bytes,
+        Err(why) => return Response::Hidden(why),
+    };
+    let data = match process(move || prepare_sticker(&bytes)).await {
+        Ok(data) => data,
+        Err(why) => return Response::Hidden(why),
+    };
+    let sticker = guild
+        .create_sticker(&ctx.http, |sticker| {
+            sticker
+                .name(name)
+                .description(description)
+                .tags(&emoji)
+                .file(AttachmentType::Bytes {
+                    data: data.into(),
+                    filename: format!("{}.png", name),
+                })
+        })
+        .await;
+    let sticker = match sticker {
+        Ok(sticker) => sticker,
+        Err(why) => {
+            println!("Unable to create sticker: {}", why);
+            return Response::Hidden(format!("Unable to create the sticker: {}", why));
+        }
+    };
+    let entry = StickerEntry::from_sticker(&sticker, guild);
+    // The sticker update event may have synced it already
+    STICKERS
+        .update(|entries| {
+            entries.retain(|e| e.id != entry.id);
+            entries.push(entry);
+        })
+        .await;
+    Response::Shown(format!("Added new sticker '{}' {}", sticker.name, emoji))
+}
+
+async fn send(
+    options: &[CommandDataOption],
+    ctx: &Context,
+    command: &ApplicationCommandInteraction,
+    guild: GuildId,
+) -> Response {
+    let query = string_option(options, "sticker").unwrap_or_default();
+    // Autocomplete fills in the id, typed names work as well
+    let sticker = STICKERS
+        .read()
+        .await
+        .iter()
+        .find(|entry| {
+            entry.guild == guild.0
+                && (entry.id.to_string() == query || entry.name.eq_ignore_ascii_case(query))
+        })
+        .cloned();
+    let sticker = match sticker {
+        Some(sticker) => sticker,
+        None => return Response::Hidden("Unable to find that sticker in this server".to_string()),
+    };
+    if let Err(why) = command
+        .channel_id
+        .send_message(&ctx.http, |m| m.sticker_id(StickerId(sticker.id)))
+        .await
+    {
+        println!("Unable to send sticker: {}", why);
+        return Response::Hidden("Unable to send the sticker".to_string());
+    }
+    Response::Hidden(format!("Sent '{}'", sticker.name))
+}
+
+pub async fn run(
+    options: &[CommandDataOption],
+    ctx: &Context,
+    command: &ApplicationCommandInteraction,
+) -> Response {
+    // Bots can only send stickers in the server they belong to
+    let guild = match command.guild_id {
+        Some(guild) => guild,
+        None => return Response::Hidden("Stickers can only be used in a server".to_string()),
+    };
+    let subcommand = match options.first() {
+        Some(subcommand) => subcommand,
+        None => return Response::Hidden("Please provide a subcommand".to_string()),
+    };
+    match subcommand.name.as_str() {
+        "create" => create(&subcommand.options, ctx, command, guild).await,
+        "send" => send(&subcommand.options, ctx, command, guild).await,
+        _ => Response::Hidden("Unknown subcommand".to_string()),
+    }
+}
+
+/// Suggests the stickers of the server matching what was typed
+pub async fn send_autocomplete(autocomplete: &AutocompleteInteraction, ctx: &Context) {
+    let typed = focused_value(&autocomplete.data.options).to_lowercase();
+    let guild = autocomplete.guild_id.map(|guild| guild.0);
+    let mut stickers: Vec<StickerEntry> = STICKERS
+        .read()
+        .await
+        .iter()
+        .filter(|entry| Some(entry.guild) == guild && entry.name.to_lowercase().contains(&typed))
+        .cloned()
+        .collect();
+    // Names starting with the typed text come first
+    stickers.sort_by_key(|entry| {
+        (
+            !entry.name.to_lowercase().starts_with(&typed),
+            entry.name.clone(),
+        )
+    });
+    let result = autocomplete
+        .create_autocomplete_response(&ctx.http, |response| {
+            for entry in stickers.iter().take(25) {
+                response.add_string_choice(
+                    format!("{} {}", entry.emoji, entry.name),
+                    entry.id.to_string(),
+                );
+            }
+            response
+        })
+        .await;
+    if let Err(why) = result {
+        println!("Unable to send autocomplete: {}", why);
+    }
+}
+
+pub fn register(command: &mut CreateApplicationCommand) -> &mut CreateApplicationCommand {
+    command
+        .name("sticker")
+        .description("Create and send stickers")
+        .create_option(|option| {
+            option
+                .name("create")
+                .description("Create a sticker in this server")
+                .kind(CommandOptionType::SubCommand)
+                .create_sub_option(|option| {
+                    option
+                        .name("name")
+                        .description("Choose a name for the sticker")
+                        .kind(CommandOptionType::String)
+                        .min_length(2)
+                        .max_length(30)
+                        .required(true)
+                })
+                .create_sub_option(|option| {
+                    option
+                        .name("emoji")
+                        .description("Standard emoji the sticker is related to")
+                        .kind(CommandOptionType::String)
+                        .required(true)
+                })
+                .create_sub_option(|option| {
+                    option
+                        .name("image")
+                        .description("Choose an image or gif for the sticker")
+                        .kind(CommandOptionType::Attachment)
+                        .required(true)
+                })
+                .create_sub_option(|option| {
+                    option
+                        .name("description")
+                        .description("Describe the sticker")
+                        .kind(CommandOptionType::String)
+                        .min_length(2)
+                        .max_length(100)
+                        .required(false)
+                })
+        })
+        .create_option(|option| {
+            option
+                .name("send")
+                .description("Send a sticker of this server")
+                .kind(CommandOptionType::SubCommand)
+                .create_sub_option(|option| {
+                    option
+                        .name("sticker")
+                        .description("Sticker to send")
+                        .kind(CommandOptionType::String)
+                        .required(true)
+                        .set_autocomplete(true)
+                })
+        })
+}
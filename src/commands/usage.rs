@@ -0,0 +1,117 @@
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use chrono::Utc;
+use lazy_static::lazy_static;
+use serde::{Deserialize, Serialize};
+
+use crate::commands::react::EMOTES;
+use crate::store::Store;
+
+// Half life of an emote use when ranking, in days
+const USAGE_HALF_LIFE: f64 = 30.0;
+
+/// Id of the bot user, set when connecting so its own reactions aren't counted
+pub static BOT_ID: AtomicU64 = AtomicU64::new(0);
+
+lazy_static! {
+    /// Usage of each emote, keyed by emote id
+    pub static ref USAGE: Store<HashMap<String, EmoteUsage>> = Store::load("data/emote_usage.json");
+}
+
+/// Where an emote was used
+pub enum Source {
+    React,
+    Message,
+    Reaction,
+}
+
+#[derive(Serialize, Deserialize, Debug, Default, Clone)]
+pub struct EmoteUsage {
+    /// Uses from every source
+    pub count: u64,
+    /// Unix timestamp of the last use
+    pub last_used: i64,
+    #[serde(default)]
+    pub reacts: u64,
+    #[serde(default)]
+    pub messages: u64,
+    #[serde(default)]
+    pub reactions: u64,
+    /// Uses by each user, keyed by user id
+    #[serde(default)]
+    pub users: HashMap<u64, u64>,
+}
+
+impl EmoteUsage {
+    /// Usage count where older uses count less
+    pub fn score(&self, now: i64) -> f64 {
+        let days = (now - self.last_used).max(0) as f64 / 86400.0;
+        self.count as f64 * 0.5f64.powf(days / USAGE_HALF_LIFE)
+    }
+}
+
+pub async fn record_use(emote: &str, user: u64, source: Source) {
+    let now = Utc::now().timestamp();
+    USAGE
+        .update(|usage| {
+            let entry = usage.entry(emote.to_string()).or_default();
+            entry.count += 1;
+            entry.last_used = now;
+            match source {
+                Source::React => entry.reacts += 1,
+                Source::Message => entry.messages += 1,
+                Source::Reaction => entry.reactions += 1,
+            }
+            *entry.users.entry(user).or_default() += 1;
+        })
+        .await;
+}
+
+/// Ids of the custom emotes written in a message, like `<:name:id>` or `<a:name:id>`
+pub fn emote_ids(content: &str) -> Vec<String> {
+    let mut ids = Vec::new();
+    for part in content.split('<').skip(1) {
+        let mention = match part.split_once('>') {
+            Some((mention, _)) => mention,
+            None => continue,
+        };
+        let mention = mention.strip_prefix('a').unwrap_or(mention);
+        let mut fields = mention.split(':');
+        if let (Some(""), Some(name), Some(id), None) =
+            (fields.next(), fields.next(), fields.next(), fields.next())
+        {
+            if !name.is_empty() && id.parse::<u64>().is_ok() {
+                ids.push(id.to_string());
+            }
+        }
+    }
+    ids
+}
+
+/// Records the registry emotes used in a message
+pub async fn record_message(user: u64, content: &str) {
+    if user == BOT_ID.load(Ordering::Relaxed) {
+        return;
+    }
+    let ids: Vec<String> = {
+        let emotes = EMOTES.read().await;
+        emote_ids(content)
+            .into_iter()
+            .filter(|id| emotes.iter().any(|emote| emote.emote == *id))
+            .collect()
+    };
+    for id in ids {
+        record_use(&id, user, Source::Message).await;
+    }
+}
+
+/// Records a reaction with a registry emote
+pub async fn record_reaction(user: u64, emote: &str) {
+    if user == BOT_ID.load(Ordering::Relaxed) {
+        return;
+    }
+    if EMOTES.read().await.iter().any(|e| e.emote == emote) {
+        record_use(emote, user, Source::Reaction).await;
+    }
+}
@@ -0,0 +1,46 @@
+//! Throughput of judging messages, run with `cargo test bench_messages -- --ignored --nocapture`
+
+use std::fs::File;
+use std::time::Instant;
+
+use crate::friday::friday::{judge_message, Friday};
+
+const COUNT: u64 = 100_000;
+
+/// Judges a burst of messages concurrently and prints the throughput,
+/// next to the old approach of reading the Friday file for every message
+#[tokio::test(flavor = "multi_thread")]
+#[ignore]
+async fn bench_messages() {
+    let start = Instant::now();
+    let tasks: Vec<_> = (0..COUNT)
+        .map(|i| tokio::spawn(judge_message(i % 500, i % 10, false, "benchmark message")))
+        .collect();
+    for task in tasks {
+        task.await.unwrap();
+    }
+    let elapsed = start.elapsed();
+    println!(
+        "In memory: {} messages in {:?} ({:.0} messages/s)",
+        COUNT,
+        elapsed,
+        COUNT as f64 / elapsed.as_secs_f64()
+    );
+
+    // Read a scratch copy, so the bot's own data is never needed or touched
+    let path = std::env::temp_dir().join("bench_friday_data.json");
+    std::fs::write(&path, serde_json::to_string(&Friday::default()).unwrap()).unwrap();
+    let start = Instant::now();
+    for _ in 0..COUNT {
+        let file = File::open(&path).unwrap();
+        let _friday: Friday = serde_json::from_reader(&file).unwrap();
+    }
+    let elapsed = start.elapsed();
+    println!(
+        "From disk: {} messages in {:?} ({:.0} messages/s)",
+        COUNT,
+        elapsed,
+        COUNT as f64 / elapsed.as_secs_f64()
+    );
+    let _ = std::fs::remove_file(&path);
+}
@@ -0,0 +1,313 @@
+use chrono::{Datelike, NaiveDate};
+use lazy_static::lazy_static;
+use serde::{Deserialize, Serialize};
+use serenity::{model::prelude::ReactionType, prelude::Context};
+use tokio::sync::RwLock;
+
+use crate::friday::friday::{get_friday_channel, now};
+use crate::store::Store;
+
+lazy_static! {
+    pub static ref CALENDAR: Store<Vec<ThemedDay>> = Store::load("data/calendar.json");
+    pub static ref CALENDAR_STATE: Store<CalendarState> = Store::load("data/calendar_state.json");
+    static ref HOLIDAYS: RwLock<Vec<ThemedDay>> = RwLock::new(Vec::new());
+}
+
+const DATE_FORMAT: &str = "%Y-%m-%d";
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum Recurrence {
+    Once,
+    Weekly,
+    Yearly,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ThemedDay {
+    pub name: String,
+    /// First occurrence of the day, formatted as YYYY-MM-DD
+    pub date: String,
+    pub recurrence: Recurrence,
+    pub announcement: String,
+    /// Unicode emoji or custom emote like <:name:id>
+    pub reaction: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct CalendarState {
+    last_announced: Option<String>,
+}
+
+impl ThemedDay {
+    pub fn occurs_on(&self, day: NaiveDate) -> bool {
+        let date = match NaiveDate::parse_from_str(&self.date, DATE_FORMAT) {
+            Ok(date) => date,
+            Err(_) => return false,
+        };
+        match self.recurrence {
+            Recurrence::Once => date == day,
+            Recurrence::Weekly => day >= date && day.weekday() == date.weekday(),
+            Recurrence::Yearly => {
+                day >= date && day.month() == date.month() && day.day() == date.day()
+            }
+        }
+    }
+
+    pub fn reaction_type(&self) -> Option<ReactionType> {
+        self.reaction
+            .as_ref()
+            .and_then(|reaction| ReactionType::try_from(reaction.as_str()).ok())
+    }
+}
+
+pub fn parse_date(date: &str) -> Option<NaiveDate> {
+    NaiveDate::parse_from_str(date, DATE_FORMAT).ok()
+}
+
+/// All themed days, from the calendar file and the holiday ICS file if there is one
+pub async fn get_themed_days() -> Vec<ThemedDay> {
+    let mut days = CALENDAR.read().await.clone();
+    days.extend(HOLIDAYS.read().await.iter().cloned());
+    days
+}
+
+/// Reads the holiday ICS file again, it is only read once a day
+pub async fn reload_holidays() {
+    let holidays = match tokio::fs::read_to_string("data/holidays.ics").await {
+        Ok(contents) => parse_ics(&contents),
+        Err(_) => Vec::new(),
+    };
+    *HOLIDAYS.write().await = holidays;
+}
+
+pub async fn todays_themes() -> Vec<ThemedDay> {
+    let today = now().date_naive();
+    get_themed_days()
+        .await
+        .into_iter()
+        .filter(|day| day.occurs_on(today))
+        .collect()
+}
+
+/// The reaction of the first themed day today that has one
+pub async fn todays_reaction() -> Option<ReactionType> {
+    let today = now().date_naive();
+    let calendar = CALENDAR.read().await;
+    let holidays = HOLIDAYS.read().await;
+    calendar
+        .iter()
+        .chain(holidays.iter())
+        .filter(|day| day.occurs_on(today))
+        .find_map(|day| day.reaction_type())
+}
+
+/// Unescapes a text value in one pass, so an escaped backslash never starts another escape
+fn unescape_ics(value: &str) -> String {
+    let mut unescaped = String::with_capacity(value.len());
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            unescaped.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n') | Some('N') => unescaped.push('\n'),
+            Some(escaped) => unescaped.push(escaped),
+            None => unescaped.push('\\'),
+        }
+    }
+    unescaped
+}
+
+/// Parses the VEVENTs of an ICS file into themed days.
+/// Supports DTSTART dates, SUMMARY, DESCRIPTION as the announcement,
+/// RRULE with FREQ=WEEKLY or FREQ=YEARLY and a custom X-REACTION property.
+/// Events that repeat any other way are skipped, rather than shown only once.
+pub fn parse_ics(contents: &str) -> Vec<ThemedDay> {
+    // Unfold lines, continuation lines start with a space or a tab
+    let mut lines: Vec<String> = Vec::new();
+    for line in contents.lines() {
+        let line = line.trim_end_matches('\r');
+        if let Some(rest) = line.strip_prefix(' ').or_else(|| line.strip_prefix('\t')) {
+            if let Some(last) = lines.last_mut() {
+                last.push_str(rest);
+                continue;
+            }
+        }
+        lines.push(line.to_string());
+    }
+
+    let mut days = Vec::new();
+    let mut event: Option<ThemedDay> = None;
+    // Recurrence of the current event that isn't supported
+    let mut unsupported: Option<String> = None;
+    for line in lines {
+        let (key, value) = match line.split_once(':') {
+            Some(pair) => pair,
+            None => continue,
+        };
+        // Strip parameters like DTSTART;VALUE=DATE
+        let key = key.split(';').next().unwrap_or(key).to_uppercase();
+        match (key.as_str(), event.as_mut()) {
+            ("BEGIN", _) if value == "VEVENT" => {
+                event = Some(ThemedDay {
+                    name: String::new(),
+                    date: String::new(),
+                    recurrence: Recurrence::Once,
+                    announcement: String::new(),
+                    reaction: None,
+                });
+                unsupported = None;
+            }
+            ("END", Some(_)) if value == "VEVENT" => {
+                let mut day = event.take().unwrap();
+                if day.name.is_empty() || parse_date(&day.date).is_none() {
+                    continue;
+                }
+                if let Some(rule) = unsupported.take() {
+                    println!("Skipping holiday '{}', {} is not supported", day.name, rule);
+                    continue;
+                }
+                if day.announcement.is_empty() {
+                    day.announcement = format!("Happy {}!", day.name);
+                }
+                days.push(day);
+            }
+            ("SUMMARY", Some(day)) => day.name = unescape_ics(value),
+            ("DESCRIPTION", Some(day)) => day.announcement = unescape_ics(value),
+            ("X-REACTION", Some(day)) => day.reaction = Some(value.to_string()),
+            ("DTSTART", Some(day)) => {
+                // Dates look like 20240101 or 20240101T000000Z
+                let date = value.get(0..8).unwrap_or_default();
+                if let Ok(date) = NaiveDate::parse_from_str(date, "%Y%m%d") {
+                    day.date = date.format(DATE_FORMAT).to_string();
+                }
+            }
+            ("RRULE", Some(day)) => {
+                for part in value.split(';') {
+                    match part.to_uppercase().as_str() {
+                        "FREQ=WEEKLY" => day.recurrence = Recurrence::Weekly,
+                        "FREQ=YEARLY" => day.recurrence = Recurrence::Yearly,
+                        rule if rule.starts_with("FREQ=") => unsupported = Some(rule.to_string()),
+                        _ => {}
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+    days
+}
+
+pub async fn calendar_controller(ctx: &Context) {
+    let today = now().date_naive().format(DATE_FORMAT).to_string();
+    if CALENDAR_STATE.read().await.last_announced.as_ref() == Some(&today) {
+        return;
+    }
+    CALENDAR_STATE
+        .update(|state| state.last_announced = Some(today))
+        .await;
+    reload_holidays().await;
+    let themes = todays_themes().await;
+    let channel = match get_friday_channel() {
+        Some(channel) if !themes.is_empty() => channel,
+        _ => return,
+    };
+    for theme in themes {
+        let message = match channel.say(&ctx.http, &theme.announcement).await {
+            Ok(message) => message,
+            Err(why) => {
+                println!("Unable to announce {}: {}", theme.name, why);
+                continue;
+            }
+        };
+        if let Some(reaction) = theme.reaction_type() {
+            if let Err(why) = message.react(&ctx.http, reaction).await {
+                println!("Unable to react to {} announcement: {}", theme.name, why);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EVENTS: &str = "BEGIN:VCALENDAR\r
+BEGIN:VEVENT\r
+DTSTART;VALUE=DATE:20240101\r
+RRULE:FREQ=YEARLY\r
+SUMMARY:New Year\r
+X-REACTION:🎆\r
+END:VEVENT\r
+BEGIN:VEVENT\r
+DTSTART:20240105T090000Z\r
+RRULE:FREQ=WEEKLY;BYDAY=FR\r
+SUMMARY:Pizza day\r
+DESCRIPTION:Pizza\\, again\\; as\r
+  every week\r
+END:VEVENT\r
+BEGIN:VEVENT\r
+DTSTART:20240301\r
+SUMMARY:Launch\r
+END:VEVENT\r
+END:VCALENDAR\r
+";
+
+    #[test]
+    fn parse_ics_reads_events() {
+        let days = parse_ics(EVENTS);
+        assert_eq!(days.len(), 3);
+
+        assert_eq!(days[0].name, "New Year");
+        assert_eq!(days[0].date, "2024-01-01");
+        assert_eq!(days[0].recurrence, Recurrence::Yearly);
+        assert_eq!(days[0].announcement, "Happy New Year!");
+        assert_eq!(days[0].reaction.as_deref(), Some("🎆"));
+
+        assert_eq!(days[1].date, "2024-01-05");
+        assert_eq!(days[1].recurrence, Recurrence::Weekly);
+        assert_eq!(days[1].announcement, "Pizza, again; as every week");
+
+        assert_eq!(days[2].recurrence, Recurrence::Once);
+        assert_eq!(days[2].reaction, None);
+    }
+
+    #[test]
+    fn parse_ics_skips_unsupported_recurrence() {
+        let contents = "BEGIN:VEVENT
+DTSTART:20240115
+RRULE:FREQ=MONTHLY
+SUMMARY:Payday
+END:VEVENT
+BEGIN:VEVENT
+DTSTART:20240116
+SUMMARY:Next
+END:VEVENT
+";
+        let days = parse_ics(contents);
+        assert_eq!(days.len(), 1);
+        assert_eq!(days[0].name, "Next");
+    }
+
+    #[test]
+    fn parse_ics_skips_incomplete_events() {
+        let contents = "BEGIN:VEVENT
+SUMMARY:No date
+END:VEVENT
+BEGIN:VEVENT
+DTSTART:20240116
+END:VEVENT
+";
+        assert!(parse_ics(contents).is_empty());
+    }
+
+    #[test]
+    fn unescape_ics_reads_left_to_right() {
+        assert_eq!(unescape_ics("a\\nb"), "a\nb");
+        assert_eq!(unescape_ics("C:\\\\new"), "C:\\new");
+        assert_eq!(unescape_ics("one\\, two\\; three"), "one, two; three");
+        assert_eq!(unescape_ics("trailing\\"), "trailing\\");
+    }
+}
@@ -0,0 +1,73 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use lazy_static::lazy_static;
+use serde::{Deserialize, Serialize};
+
+use crate::store::Store;
+
+const HOUR: Duration = Duration::from_secs(60 * 60);
+
+lazy_static! {
+    pub static ref CONFIG: Store<FridayConfig> = Store::load("data/friday_config.json");
+    // Times the bot has reacted to each user, used for the hourly limit
+    static ref REACTIONS: Mutex<HashMap<u64, Vec<Instant>>> = Mutex::new(HashMap::new());
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct FridayConfig {
+    /// If not empty, only these channels get reactions
+    pub allowed_channels: Vec<u64>,
+    /// Channels that never get reactions
+    pub denied_channels: Vec<u64>,
+    /// Users who don't want to be reacted to
+    pub opted_out: Vec<u64>,
+    /// Max reactions per user per hour, 0 means unlimited
+    pub hourly_limit: u32,
+}
+
+impl Default for FridayConfig {
+    fn default() -> Self {
+        Self {
+            allowed_channels: Vec::new(),
+            denied_channels: Vec::new(),
+            opted_out: Vec::new(),
+            hourly_limit: 10,
+        }
+    }
+}
+
+impl FridayConfig {
+    pub fn channel_allowed(&self, channel: u64) -> bool {
+        if self.denied_channels.contains(&channel) {
+            return false;
+        }
+        self.allowed_channels.is_empty() || self.allowed_channels.contains(&channel)
+    }
+}
+
+/// Records a reaction to the user if they are still under the hourly limit
+pub fn take_reaction(user: u64, limit: u32) -> bool {
+    if limit == 0 {
+        return true;
+    }
+    let mut reactions = REACTIONS.lock().unwrap();
+    let times = reactions.entry(user).or_default();
+    let now = Instant::now();
+    times.retain(|time| now.duration_since(*time) < HOUR);
+    if times.len() >= limit as usize {
+        return false;
+    }
+    times.push(now);
+    true
+}
+
+/// Forgets the users whose reactions are all older than the window
+pub fn prune_reactions() {
+    let now = Instant::now();
+    REACTIONS
+        .lock()
+        .unwrap()
+        .retain(|_, times| times.iter().any(|time| now.duration_since(*time) < HOUR));
+}
@@ -1,54 +1,63 @@
 use std::{fs::File, io::Write};
 
-use chrono::{Datelike, FixedOffset, Utc};
+use chrono::{DateTime, Datelike, FixedOffset, Utc};
+use lazy_static::lazy_static;
 use serde::{Deserialize, Serialize};
 use serenity::{
-    model::prelude::{ChannelId, GuildChannel, GuildId, Message, ReactionType},
+    model::prelude::{ChannelId, Message, ReactionType},
     prelude::Context,
 };
 
-#[derive(Serialize, Deserialize, Debug)]
-struct Friday {
+use crate::friday::calendar::todays_reaction;
+use crate::friday::config::{prune_reactions, take_reaction, CONFIG};
+use crate::store::Store;
+
+lazy_static! {
+    pub static ref FRIDAY: Store<Friday> = Store::load("data/friday_data.json");
+}
+
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct Friday {
     pub friday: bool,
     pub angels: Vec<u64>,
+    /// Users pardoned permanently, these aren't reset when Friday starts
+    #[serde(default)]
+    pub permanent: Vec<u64>,
+    /// Users pardoned before Friday started, they become angels when it starts
+    #[serde(default)]
+    pub pending: Vec<u64>,
+    /// Day Friday was force started on, it lasts until that day ends
+    #[serde(default)]
+    pub forced_on: Option<String>,
 }
 
-fn is_friday() -> bool {
+/// The current time in the bot's timezone
+pub fn now() -> DateTime<FixedOffset> {
     let offset = FixedOffset::east_opt(2 * 3600).unwrap();
     let now_utc = Utc::now();
-    let now = now_utc.with_timezone(&offset);
+    now_utc.with_timezone(&offset)
+}
+
+pub fn is_friday() -> bool {
+    let now = now();
     if now.weekday().to_string() == "Fri".to_string() {
         return true;
     }
     false
 }
 
-fn get_friday() -> Friday {
-    let file = File::open("data/friday_data.json");
-    let friday = match file {
-        Ok(f) => {
-            let friday: Friday = serde_json::from_reader(&f).expect("Unable to read file");
-            friday
-        }
-        Err(_) => {
-            let friday: Friday = Friday {
-                friday: false,
-                angels: Vec::new(),
-            };
-            let contents = serde_json::to_string_pretty(&friday).unwrap();
-            File::create("data/friday_data.json")
-                .unwrap()
-                .write_all(contents.as_bytes())
-                .unwrap();
-            friday
-        }
-    };
-    friday
+fn today() -> String {
+    now().date_naive().format("%Y-%m-%d").to_string()
 }
 
-async fn get_friday_channel(ctx: &Context) -> GuildChannel {
+/// Whether Friday rules apply, because it is Friday or Friday was force started today
+pub async fn friday_active() -> bool {
+    is_friday() || FRIDAY.read().await.forced_on == Some(today())
+}
+
+pub fn read_friday_channel() -> (u64, u64) {
     let file = File::open("data/friday_channel.json");
-    let (guild_id, channel_id) = match file {
+    match file {
         Ok(file) => {
             let content: (u64, u64) = serde_json::from_reader(file).expect("Unable to read file");
             content
@@ -62,90 +71,206 @@ async fn get_friday_channel(ctx: &Context) -> GuildChannel {
                 .unwrap();
             channel
         }
-    };
-
-    let guild_id = GuildId(guild_id);
-    let guild = guild_id.to_partial_guild(&ctx.http).await.unwrap();
-    let channel = guild
-        .channels(&ctx.http)
-        .await
-        .unwrap()
-        .get(&ChannelId(channel_id))
-        .unwrap()
-        .clone();
-    channel
+    }
 }
 
-fn write_friday(friday: Friday) {
-    let contents = serde_json::to_string_pretty(&friday).unwrap();
-    File::create("data/friday_data.json")
+pub fn set_friday_channel(guild_id: u64, channel_id: u64) {
+    let contents = serde_json::to_string_pretty(&(guild_id, channel_id)).unwrap();
+    File::create("data/friday_channel.json")
         .unwrap()
         .write_all(contents.as_bytes())
         .unwrap();
 }
 
-pub fn add_user(user: u64) {
-    let mut friday = get_friday();
-    friday.angels.push(user);
-    println!("{:?}", friday.angels);
-    write_friday(friday);
+/// The announcement channel, if one was set
+pub fn get_friday_channel() -> Option<ChannelId> {
+    match read_friday_channel() {
+        (_, 0) => None,
+        (_, channel_id) => Some(ChannelId(channel_id)),
+    }
 }
 
-pub fn remove_user(user: u64) {
-    let mut friday = get_friday();
-    friday.angels.retain(|&x| x != user);
-    write_friday(friday);
+/// Pardons a user for the current Friday, or for the next one if it hasn't started yet.
+/// Returns whether the pardon is for the current Friday.
+pub async fn add_user(user: u64) -> bool {
+    let current = started_today().await;
+    FRIDAY
+        .update(|friday| {
+            let angels = if current {
+                &mut friday.angels
+            } else {
+                &mut friday.pending
+            };
+            if !angels.contains(&user) {
+                angels.push(user);
+            }
+            println!("{:?}", angels);
+        })
+        .await;
+    current
 }
 
-pub async fn friday_controller(ctx: &Context) {
-    let mut friday = get_friday();
-    if friday.friday {
-        if !is_friday() {
-            friday.friday = false;
-            write_friday(friday);
-        }
-    } else {
-        if is_friday() {
+pub async fn add_permanent_user(user: u64) {
+    FRIDAY
+        .update(|friday| {
+            if !friday.permanent.contains(&user) {
+                friday.permanent.push(user);
+            }
+        })
+        .await;
+}
+
+/// Removes a user from the angels and permanent pardons, returns false if they weren't pardoned
+pub async fn remove_user(user: u64) -> bool {
+    FRIDAY
+        .update(|friday| {
+            if !friday.angels.contains(&user)
+                && !friday.permanent.contains(&user)
+                && !friday.pending.contains(&user)
+            {
+                return false;
+            }
+            friday.angels.retain(|&x| x != user);
+            friday.permanent.retain(|&x| x != user);
+            friday.pending.retain(|&x| x != user);
+            true
+        })
+        .await
+}
+
+pub async fn get_angels() -> Vec<u64> {
+    FRIDAY.read().await.angels.clone()
+}
+
+pub async fn get_pending_angels() -> Vec<u64> {
+    FRIDAY.read().await.pending.clone()
+}
+
+pub async fn get_permanent_angels() -> Vec<u64> {
+    FRIDAY.read().await.permanent.clone()
+}
+
+pub async fn friday_started() -> bool {
+    FRIDAY.read().await.friday
+}
+
+/// Starts friday, replacing the angels with the users pardoned for it and announcing it.
+/// A forced start works on any day and lasts until the end of the day.
+pub async fn start_friday(ctx: &Context, forced: bool) {
+    FRIDAY
+        .update(|friday| {
             friday.friday = true;
-            friday.angels = Vec::new();
-            write_friday(friday);
-            let channel = get_friday_channel(ctx).await;
-            channel.say(&ctx.http, "Fiberoptic Friday!").await.unwrap();
-            channel
-                .say(&ctx.http, "https://tenor.com/view/fiber-optic-friday-eat-the-optic-cable-cable-shark-fibre-shark-gif-25548843")
-                .await
-                .unwrap();
+            friday.angels = std::mem::take(&mut friday.pending);
+            friday.forced_on = if forced { Some(today()) } else { None };
+        })
+        .await;
+    let channel = match get_friday_channel() {
+        Some(channel) => channel,
+        None => {
+            println!("Unable to announce Friday: no channel set");
+            return;
+        }
+    };
+    for content in [
+        "Fiberoptic Friday!",
+        "https://tenor.com/view/fiber-optic-friday-eat-the-optic-cable-cable-shark-fibre-shark-gif-25548843",
+    ] {
+        if let Err(why) = channel.say(&ctx.http, content).await {
+            println!("Unable to announce Friday: {}", why);
+            return;
+        }
+    }
+}
+
+async fn end_friday() {
+    FRIDAY
+        .update(|friday| {
+            friday.friday = false;
+            friday.forced_on = None;
+        })
+        .await;
+}
+
+/// Whether Friday was started, a forced start only counts on the day it was made
+async fn started_today() -> bool {
+    let friday = FRIDAY.read().await;
+    friday.friday && (friday.forced_on.is_none() || friday.forced_on == Some(today()))
+}
+
+pub async fn friday_controller(ctx: &Context) {
+    prune_reactions();
+    if started_today().await {
+        if !friday_active().await {
+            end_friday().await;
         }
+    } else if is_friday() {
+        start_friday(ctx, false).await;
+    } else if friday_started().await {
+        // Forced on an earlier day that wasn't a Friday
+        end_friday().await;
     }
 }
 
-fn is_sinner(user: u64) -> bool {
-    let friday = get_friday();
+async fn is_sinner(user: u64) -> bool {
+    let friday = FRIDAY.read().await;
     !friday.angels.contains(&user)
+        && !friday.permanent.contains(&user)
+        && !friday.pending.contains(&user)
+}
+
+pub enum Verdict {
+    Ignore,
+    Pardon,
+    React(ReactionType),
 }
 
-async fn friday_react(message: &Message, friday: bool, ctx: &Context) {
+/// Decides what to do with a message, only uses the in-memory state
+pub async fn judge_message(user: u64, channel: u64, bot: bool, content: &str) -> Verdict {
+    let friday = friday_active().await;
+    let sinner = is_sinner(user).await;
+    if !sinner || bot {
+        return Verdict::Ignore;
+    }
+    if friday && content.contains("tenor") && content.contains("fiber-optic-friday") {
+        return Verdict::Pardon;
+    }
+    {
+        let config = CONFIG.read().await;
+        if !config.channel_allowed(channel) || config.opted_out.contains(&user) {
+            return Verdict::Ignore;
+        }
+        if !take_reaction(user, config.hourly_limit) {
+            return Verdict::Ignore;
+        }
+    }
     let emote = if friday {
         ReactionType::try_from("<:Friday:1021853202964029470>").unwrap()
+    } else if let Some(reaction) = todays_reaction().await {
+        reaction
     } else {
         ReactionType::Unicode("🤓".to_string())
     };
-    message.react(&ctx.http, emote).await.unwrap();
+    Verdict::React(emote)
 }
 
 pub async fn handle_message(message: &Message, ctx: &Context) {
     let user = message.author.id.0;
-    let friday = is_friday();
-    let sinner = is_sinner(user);
-    println!("{} {} {}", friday, sinner, user);
-    if sinner && !message.author.bot {
-        if friday
-            && message.content.contains("tenor")
-            && message.content.contains("fiber-optic-friday")
-        {
-            add_user(user);
-            return;
+    let verdict = judge_message(
+        user,
+        message.channel_id.0,
+        message.author.bot,
+        &message.content,
+    )
+    .await;
+    match verdict {
+        Verdict::Ignore => {}
+        Verdict::Pardon => {
+            add_user(user).await;
+        }
+        Verdict::React(emote) => {
+            if let Err(why) = message.react(&ctx.http, emote).await {
+                println!("Unable to react to message: {}", why);
+            }
         }
-        friday_react(message, friday, ctx).await;
     }
 }
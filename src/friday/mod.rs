@@ -1 +1,5 @@
+#[cfg(test)]
+mod bench;
+pub mod calendar;
+pub mod config;
 pub mod friday;
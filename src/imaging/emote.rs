@@ -0,0 +1,192 @@
+use std::io::Cursor;
+
+use image::codecs::gif::{GifDecoder, GifEncoder, Repeat};
+use image::codecs::png::PngDecoder;
+use image::codecs::webp::WebPDecoder;
+use image::imageops::FilterType;
+use image::io::Reader;
+use image::{
+    AnimationDecoder, Delay, Frame, Frames, ImageFormat, ImageOutputFormat, ImageResult, RgbaImage,
+};
+
+use crate::imaging::hash::dhash;
+
+// Discord rejects emotes larger than 256 KB
+pub const MAX_EMOTE_BYTES: usize = 256 * 1024;
+// Emotes are never shown larger than this
+const MAX_EMOTE_SIZE: u32 = 128;
+const MIN_EMOTE_SIZE: u32 = 32;
+// Larger images are refused before decoding, they take too much memory
+const MAX_INPUT_SIZE: u32 = 4096;
+// Animations are cut off after this many frames, or once their frames hold this many pixels
+const MAX_FRAMES: usize = 150;
+const MAX_PIXELS: u64 = 64 * 1024 * 1024;
+
+pub struct ProcessedImage {
+    pub data: Vec<u8>,
+    pub animated: bool,
+    /// Perceptual hash, to find near duplicates
+    pub hash: u64,
+}
+
+impl ProcessedImage {
+    /// The image formatted as a data url for the emoji API
+    pub fn data_url(&self) -> String {
+        let mime = if self.animated { "gif" } else { "png" };
+        format!("data:image/{};base64,{}", mime, base64::encode(&self.data))
+    }
+}
+
+/// Decodes any supported image into its frames, animated images have more than one.
+/// The size is checked before decoding, and long animations are cut off.
+pub fn decode_frames(bytes: &[u8]) -> Result<Vec<Frame>, String> {
+    let (width, height) = Reader::new(Cursor::new(bytes))
+        .with_guessed_format()
+        .ok()
+        .and_then(|reader| reader.into_dimensions().ok())
+        .ok_or_else(|| "Unable to read the image".to_string())?;
+    if width > MAX_INPUT_SIZE || height > MAX_INPUT_SIZE {
+        return Err(format!(
+            "Images can be at most {}x{} pixels",
+            MAX_INPUT_SIZE, MAX_INPUT_SIZE
+        ));
+    }
+    let max_frames =
+        ((MAX_PIXELS / (width as u64 * height as u64).max(1)) as usize).clamp(1, MAX_FRAMES);
+    let format = image::guess_format(bytes).map_err(|_| "Unsupported image format".to_string())?;
+    let frames = match format {
+        ImageFormat::Gif => GifDecoder::new(Cursor::new(bytes))
+            .and_then(|decoder| take_frames(decoder.into_frames(), max_frames)),
+        ImageFormat::WebP => WebPDecoder::new(Cursor::new(bytes)).and_then(|decoder| {
+            if decoder.has_animation() {
+                take_frames(decoder.into_frames(), max_frames)
+            } else {
+                image::load_from_memory(bytes).map(|image| vec![still_frame(image.into_rgba8())])
+            }
+        }),
+        ImageFormat::Png => PngDecoder::new(Cursor::new(bytes)).and_then(|decoder| {
+            if decoder.is_apng() {
+                take_frames(decoder.apng().into_frames(), max_frames)
+            } else {
+                image::load_from_memory(bytes).map(|image| vec![still_frame(image.into_rgba8())])
+            }
+        }),
+        _ => image::load_from_memory(bytes).map(|image| vec![still_frame(image.into_rgba8())]),
+    };
+    match frames {
+        Ok(frames) if !frames.is_empty() => Ok(frames),
+        _ => Err("Unable to read the image".to_string()),
+    }
+}
+
+/// Decodes at most the given number of frames, the rest are never decoded
+fn take_frames(frames: Frames, max_frames: usize) -> ImageResult<Vec<Frame>> {
+    frames.take(max_frames).collect()
+}
+
+fn still_frame(image: RgbaImage) -> Frame {
+    Frame::from_parts(image, 0, 0, Delay::from_numer_denom_ms(0, 1))
+}
+
+/// Bounding box of the non transparent pixels in all frames
+fn content_bounds(frames: &[Frame]) -> Option<(u32, u32, u32, u32)> {
+    let mut bounds: Option<(u32, u32, u32, u32)> = None;
+    for frame in frames {
+        for (x, y, pixel) in frame.buffer().enumerate_pixels() {
+            if pixel[3] == 0 {
+                continue;
+            }
+            bounds = Some(match bounds {
+                Some((x0, y0, x1, y1)) => (x0.min(x), y0.min(y), x1.max(x), y1.max(y)),
+                None => (x, y, x, y),
+            });
+        }
+    }
+    bounds
+}
+
+/// Crops away transparent borders and pads the frames to a square
+pub fn square_frames(frames: Vec<Frame>) -> Vec<Frame> {
+    let first = frames[0].buffer();
+    let (x0, y0, x1, y1) =
+        content_bounds(&frames).unwrap_or((0, 0, first.width() - 1, first.height() - 1));
+    let (width, height) = (x1 - x0 + 1, y1 - y0 + 1);
+    let side = width.max(height);
+    let (left, top) = ((side - width) / 2, (side - height) / 2);
+    frames
+        .into_iter()
+        .map(|frame| {
+            let delay = frame.delay();
+            let cropped = image::imageops::crop_imm(frame.buffer(), x0, y0, width, height);
+            let mut square = RgbaImage::new(side, side);
+            image::imageops::overlay(&mut square, &*cropped, left.into(), top.into());
+            Frame::from_parts(square, 0, 0, delay)
+        })
+        .collect()
+}
+
+/// Resizes every frame, keeping their delays
+pub fn resize_frames(frames: &[Frame], width: u32, height: u32) -> Vec<Frame> {
+    frames
+        .iter()
+        .map(|frame| {
+            let resized =
+                image::imageops::resize(frame.buffer(), width, height, FilterType::Triangle);
+            Frame::from_parts(resized, 0, 0, frame.delay())
+        })
+        .collect()
+}
+
+/// Scales the frames down to fit in a square of the size, keeping their aspect ratio
+pub fn shrink_frames(frames: Vec<Frame>, max_size: u32) -> Vec<Frame> {
+    let (width, height) = frames[0].buffer().dimensions();
+    if width <= max_size && height <= max_size {
+        return frames;
+    }
+    let ratio = max_size as f32 / width.max(height) as f32;
+    resize_frames(
+        &frames,
+        ((width as f32 * ratio) as u32).max(1),
+        ((height as f32 * ratio) as u32).max(1),
+    )
+}
+
+/// Encodes a single frame as a png, or multiple frames as a looping gif
+pub fn encode_frames(frames: Vec<Frame>) -> Result<Vec<u8>, String> {
+    let mut buffer = Vec::new();
+    if frames.len() == 1 {
+        let image = frames.into_iter().next().unwrap().into_buffer();
+        image
+            .write_to(&mut Cursor::new(&mut buffer), ImageOutputFormat::Png)
+            .map_err(|_| "Unable to encode the image".to_string())?;
+    } else {
+        let mut encoder = GifEncoder::new_with_speed(&mut buffer, 10);
+        encoder
+            .set_repeat(Repeat::Infinite)
+            .and_then(|_| encoder.encode_frames(frames))
+            .map_err(|_| "Unable to encode the gif".to_string())?;
+    }
+    Ok(buffer)
+}
+
+/// Turns any supported image into a square emote under Discord's size limit.
+pub fn prepare_emote(bytes: &[u8]) -> Result<ProcessedImage, String> {
+    let frames = square_frames(decode_frames(bytes)?);
+    let animated = frames.len() > 1;
+    let hash = dhash(frames[0].buffer());
+    let mut size = frames[0].buffer().width().min(MAX_EMOTE_SIZE);
+    loop {
+        let data = encode_frames(resize_frames(&frames, size, size))?;
+        if data.len() <= MAX_EMOTE_BYTES {
+            return Ok(ProcessedImage {
+                data,
+                animated,
+                hash,
+            });
+        }
+        if size <= MIN_EMOTE_SIZE {
+            return Err("The image is too large even after resizing".to_string());
+        }
+        size = (size * 3 / 4).max(MIN_EMOTE_SIZE);
+    }
+}
@@ -0,0 +1,83 @@
+use image::imageops::FilterType;
+use image::{GrayImage, Luma, RgbaImage};
+
+use crate::imaging::emote::{decode_frames, square_frames};
+
+// Hashes this many bits apart are considered the same image
+pub const SIMILAR_DISTANCE: u32 = 6;
+
+/// Difference hash of an image, similar looking images get hashes with few differing bits.
+/// Transparent pixels count as white, so the same emote on a different background still matches.
+pub fn dhash(image: &RgbaImage) -> u64 {
+    let gray = GrayImage::from_fn(image.width(), image.height(), |x, y| {
+        let [r, g, b, a] = image.get_pixel(x, y).0;
+        let luma = 0.299 * r as f32 + 0.587 * g as f32 + 0.114 * b as f32;
+        let alpha = a as f32 / 255.0;
+        Luma([(luma * alpha + 255.0 * (1.0 - alpha)) as u8])
+    });
+    let small = image::imageops::resize(&gray, 9, 8, FilterType::Triangle);
+    let mut hash = 0u64;
+    for y in 0..8 {
+        for x in 0..8 {
+            hash <<= 1;
+            if small.get_pixel(x, y)[0] > small.get_pixel(x + 1, y)[0] {
+                hash |= 1;
+            }
+        }
+    }
+    hash
+}
+
+/// Hash of the first frame, cropped the same way emotes are
+pub fn image_hash(bytes: &[u8]) -> Result<u64, String> {
+    let frames = square_frames(decode_frames(bytes)?);
+    Ok(dhash(frames[0].buffer()))
+}
+
+pub fn hash_distance(a: u64, b: u64) -> u32 {
+    (a ^ b).count_ones()
+}
+
+#[cfg(test)]
+mod tests {
+    use image::Rgba;
+
+    use super::*;
+
+    /// Dark on the left, light on the right
+    fn gradient(width: u32, height: u32) -> RgbaImage {
+        RgbaImage::from_fn(width, height, |x, _| {
+            let value = (x * 255 / (width - 1)) as u8;
+            Rgba([value, value, value, 255])
+        })
+    }
+
+    #[test]
+    fn hash_distance_counts_differing_bits() {
+        assert_eq!(hash_distance(0, 0), 0);
+        assert_eq!(hash_distance(0b1011, 0b0001), 2);
+        assert_eq!(hash_distance(0, u64::MAX), 64);
+        assert_eq!(hash_distance(u64::MAX, 0), 64);
+    }
+
+    #[test]
+    fn dhash_compares_neighbouring_pixels() {
+        // Every pixel is darker than the one on its right, so no bit is set
+        assert_eq!(dhash(&gradient(64, 64)), 0);
+        let mirrored = image::imageops::flip_horizontal(&gradient(64, 64));
+        assert_eq!(dhash(&mirrored), u64::MAX);
+    }
+
+    #[test]
+    fn dhash_ignores_size() {
+        let distance = hash_distance(dhash(&gradient(64, 64)), dhash(&gradient(300, 120)));
+        assert!(distance <= SIMILAR_DISTANCE);
+    }
+
+    #[test]
+    fn dhash_treats_transparency_as_white() {
+        let transparent = RgbaImage::from_pixel(32, 32, Rgba([0, 0, 0, 0]));
+        let white = RgbaImage::from_pixel(32, 32, Rgba([255, 255, 255, 255]));
+        assert_eq!(dhash(&transparent), dhash(&white));
+    }
+}
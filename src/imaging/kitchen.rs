@@ -0,0 +1,94 @@
+use image::{Frame, RgbaImage};
+
+use crate::imaging::emote::{decode_frames, encode_frames, resize_frames, square_frames};
+
+// Both emotes are brought to this size before combining
+const PART_SIZE: u32 = 128;
+// Longer animations are cut off, combining them is slow
+const MAX_FRAMES: usize = 100;
+
+pub enum Combine {
+    /// The emotes next to each other
+    SideBySide,
+    /// The second emote small in the bottom right corner of the first
+    Overlay,
+    /// Both emotes half see-through on top of each other
+    Blend,
+}
+
+fn load_part(bytes: &[u8]) -> Result<Vec<Frame>, String> {
+    let mut frames = square_frames(decode_frames(bytes)?);
+    frames.truncate(MAX_FRAMES);
+    Ok(resize_frames(&frames, PART_SIZE, PART_SIZE))
+}
+
+/// Averages the two images, weighing each pixel by how opaque it is
+fn blend(first: &RgbaImage, second: &RgbaImage) -> RgbaImage {
+    RgbaImage::from_fn(first.width(), first.height(), |x, y| {
+        let (a, b) = (first.get_pixel(x, y), second.get_pixel(x, y));
+        let (weight_a, weight_b) = (a[3] as u32, b[3] as u32);
+        let mut pixel = *a;
+        for channel in 0..3 {
+            pixel[channel] = (a[channel] as u32 * weight_a + b[channel] as u32 * weight_b)
+                .checked_div(weight_a + weight_b)
+                .unwrap_or(0) as u8;
+        }
+        pixel[3] = a[3].max(b[3]);
+        pixel
+    })
+}
+
+impl Combine {
+    fn apply(&self, first: &RgbaImage, second: &RgbaImage) -> RgbaImage {
+        match self {
+            Combine::SideBySide => {
+                let mut image = RgbaImage::new(PART_SIZE * 2, PART_SIZE);
+                image::imageops::overlay(&mut image, first, 0, 0);
+                image::imageops::overlay(&mut image, second, PART_SIZE.into(), 0);
+                image
+            }
+            Combine::Overlay => {
+                let small = PART_SIZE * 3 / 5;
+                let second = image::imageops::resize(
+                    second,
+                    small,
+                    small,
+                    image::imageops::FilterType::Triangle,
+                );
+                let mut image = first.clone();
+                let offset = (PART_SIZE - small).into();
+                image::imageops::overlay(&mut image, &second, offset, offset);
+                image
+            }
+            Combine::Blend => blend(first, second),
+        }
+    }
+}
+
+/// Combines two emotes into one image. Animated emotes loop along with the longer one,
+/// whose frame timing is kept.
+pub fn combine_emotes(
+    first: &[u8],
+    second: &[u8],
+    combine: Combine,
+) -> Result<(Vec<u8>, bool), String> {
+    let (first, second) = (load_part(first)?, load_part(second)?);
+    let timing = if first.len() >= second.len() {
+        &first
+    } else {
+        &second
+    };
+    let frames: Vec<Frame> = timing
+        .iter()
+        .enumerate()
+        .map(|(index, frame)| {
+            let image = combine.apply(
+                first[index % first.len()].buffer(),
+                second[index % second.len()].buffer(),
+            );
+            Frame::from_parts(image, 0, 0, frame.delay())
+        })
+        .collect();
+    let animated = frames.len() > 1;
+    Ok((encode_frames(frames)?, animated))
+}
@@ -0,0 +1,116 @@
+use image::{Frame, Rgba, RgbaImage};
+use imageproc::drawing::{draw_text_mut, text_size};
+use rusttype::{Font, Scale};
+
+use crate::imaging::emote::{decode_frames, encode_frames, shrink_frames};
+
+const FONT: &[u8] = include_bytes!("../commands/chess/resources/fonts/SegoeUIBold.ttf");
+const FILL: Rgba<u8> = Rgba([255, 255, 255, 255]);
+const OUTLINE: Rgba<u8> = Rgba([0, 0, 0, 255]);
+const MAX_STILL_SIZE: u32 = 512;
+const MAX_ANIMATED_SIZE: u32 = 320;
+const MIN_FONT_SIZE: f32 = 12.0;
+// Each caption may cover at most this part of the image height
+const MAX_CAPTION_HEIGHT: f32 = 0.3;
+
+/// Splits the text into lines no wider than the width, words longer than a line get their own
+fn wrap_text(text: &str, font: &Font, scale: Scale, width: i32) -> Vec<String> {
+    let mut lines: Vec<String> = Vec::new();
+    let mut line = String::new();
+    for word in text.split_whitespace() {
+        let candidate = if line.is_empty() {
+            word.to_string()
+        } else {
+            format!("{} {}", line, word)
+        };
+        if line.is_empty() || text_size(scale, font, &candidate).0 <= width {
+            line = candidate;
+        } else {
+            lines.push(std::mem::replace(&mut line, word.to_string()));
+        }
+    }
+    if !line.is_empty() {
+        lines.push(line);
+    }
+    lines
+}
+
+fn line_height(font: &Font, scale: Scale) -> i32 {
+    let v_metrics = font.v_metrics(scale);
+    (v_metrics.ascent - v_metrics.descent + v_metrics.line_gap).ceil() as i32
+}
+
+/// The largest font size at which the text fits in the width and the caption height
+fn fit_text(text: &str, font: &Font, width: i32, height: i32) -> (Scale, Vec<String>) {
+    let mut size = height as f32 / 6.0;
+    loop {
+        let scale = Scale::uniform(size);
+        let lines = wrap_text(text, font, scale, width);
+        let fits = lines
+            .iter()
+            .all(|line| text_size(scale, font, line).0 <= width)
+            && lines.len() as i32 * line_height(font, scale)
+                <= (height as f32 * MAX_CAPTION_HEIGHT) as i32;
+        if fits || size <= MIN_FONT_SIZE {
+            return (scale, lines);
+        }
+        size = (size * 0.9).max(MIN_FONT_SIZE);
+    }
+}
+
+/// Draws centered lines of white text with a black outline, starting at the top
+fn draw_caption(canvas: &mut RgbaImage, font: &Font, scale: Scale, lines: &[String], top: i32) {
+    let outline = (scale.y / 16.0).ceil().max(1.0) as i32;
+    let height = line_height(font, scale);
+    for (index, line) in lines.iter().enumerate() {
+        let x = (canvas.width() as i32 - text_size(scale, font, line).0) / 2;
+        let y = top + index as i32 * height;
+        for dx in -outline..=outline {
+            for dy in -outline..=outline {
+                if dx * dx + dy * dy <= outline * outline {
+                    draw_text_mut(canvas, OUTLINE, x + dx, y + dy, scale, font, line);
+                }
+            }
+        }
+        draw_text_mut(canvas, FILL, x, y, scale, font, line);
+    }
+}
+
+/// Captions the image with top and bottom text in the classic meme style.
+pub fn render_meme(bytes: &[u8], top: &str, bottom: &str) -> Result<(Vec<u8>, bool), String> {
+    let frames = decode_frames(bytes)?;
+    let animated = frames.len() > 1;
+    let max_size = if animated {
+        MAX_ANIMATED_SIZE
+    } else {
+        MAX_STILL_SIZE
+    };
+    let frames = shrink_frames(frames, max_size);
+    let (width, height) = frames[0].buffer().dimensions();
+    let font = Font::try_from_bytes(FONT).ok_or("Unable to load the font")?;
+    // The captions are drawn once and laid over every frame
+    let mut caption = RgbaImage::new(width, height);
+    let margin = (width.min(height) / 20) as i32;
+    let text_width = width as i32 - 2 * margin;
+    let top = top.trim().to_uppercase();
+    if !top.is_empty() {
+        let (scale, lines) = fit_text(&top, &font, text_width, height as i32);
+        draw_caption(&mut caption, &font, scale, &lines, margin);
+    }
+    let bottom = bottom.trim().to_uppercase();
+    if !bottom.is_empty() {
+        let (scale, lines) = fit_text(&bottom, &font, text_width, height as i32);
+        let start = height as i32 - margin - lines.len() as i32 * line_height(&font, scale);
+        draw_caption(&mut caption, &font, scale, &lines, start);
+    }
+    let frames = frames
+        .into_iter()
+        .map(|frame| {
+            let delay = frame.delay();
+            let mut image = frame.into_buffer();
+            image::imageops::overlay(&mut image, &caption, 0, 0);
+            Frame::from_parts(image, 0, 0, delay)
+        })
+        .collect();
+    Ok((encode_frames(frames)?, animated))
+}
@@ -0,0 +1,73 @@
+//! Image processing for emotes, stickers, memes and edits.
+//! Results are a png, or a gif for animated images, together with whether they are animated.
+//! Larger images are scaled down first, animations more so since gifs are slow to encode.
+//! Processing big gifs is still slow, so it runs on a blocking thread through `process`.
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use lazy_static::lazy_static;
+use tokio::sync::Semaphore;
+
+pub mod emote;
+pub mod hash;
+pub mod kitchen;
+pub mod meme;
+pub mod sticker;
+pub mod transform;
+
+// Larger files are not downloaded at all
+const MAX_DOWNLOAD_BYTES: usize = 8 * 1024 * 1024;
+// Processing that takes longer is given up on, the blocking thread finishes on its own
+const PROCESS_TIMEOUT: Duration = Duration::from_secs(20);
+// Images processed at the same time, the others wait their turn
+const MAX_JOBS: usize = 2;
+
+lazy_static! {
+    /// Held by each job until its blocking thread is done, even after the time limit
+    static ref JOBS: Arc<Semaphore> = Arc::new(Semaphore::new(MAX_JOBS));
+}
+
+/// Downloads an image, refusing files that are too large
+pub async fn download_image(url: &str) -> Result<Vec<u8>, String> {
+    let response = reqwest::get(url)
+        .await
+        .and_then(|response| response.error_for_status())
+        .map_err(|why| format!("Unable to download the image: {}", why))?;
+    if response
+        .content_length()
+        .is_some_and(|length| length as usize > MAX_DOWNLOAD_BYTES)
+    {
+        return Err("The image is too large".to_string());
+    }
+    let bytes = response
+        .bytes()
+        .await
+        .map_err(|why| format!("Unable to download the image: {}", why))?;
+    if bytes.len() > MAX_DOWNLOAD_BYTES {
+        return Err("The image is too large".to_string());
+    }
+    Ok(bytes.to_vec())
+}
+
+/// Runs the processing on a blocking thread once a job slot is free,
+/// giving up after the time limit
+pub async fn process<T, F>(f: F) -> Result<T, String>
+where
+    T: Send + 'static,
+    F: FnOnce() -> Result<T, String> + Send + 'static,
+{
+    let job = async {
+        let permit = JOBS.clone().acquire_owned().await;
+        tokio::task::spawn_blocking(move || {
+            let _permit = permit;
+            f()
+        })
+        .await
+    };
+    match tokio::time::timeout(PROCESS_TIMEOUT, job).await {
+        Ok(Ok(result)) => result,
+        Ok(Err(_)) => Err("Unable to process the image".to_string()),
+        Err(_) => Err("The image took too long to process".to_string()),
+    }
+}
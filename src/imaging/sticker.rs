@@ -0,0 +1,91 @@
+use std::io::Cursor;
+
+use image::{Delay, Frame, ImageOutputFormat};
+use png::{BitDepth, ColorType, Encoder};
+
+use crate::imaging::emote::{decode_frames, resize_frames, square_frames};
+
+// Discord rejects stickers larger than 512 KB
+pub const MAX_STICKER_BYTES: usize = 512 * 1024;
+// Stickers have to be exactly this size
+const STICKER_SIZE: u32 = 320;
+
+/// Encodes the frames as a looping apng, all frames must have the same size
+fn encode_apng(frames: &[Frame]) -> Result<Vec<u8>, String> {
+    let first = frames[0].buffer();
+    let mut buffer = Vec::new();
+    let mut encoder = Encoder::new(&mut buffer, first.width(), first.height());
+    encoder.set_color(ColorType::Rgba);
+    encoder.set_depth(BitDepth::Eight);
+    encoder
+        .set_animated(frames.len() as u32, 0)
+        .map_err(|_| "Unable to encode the animation".to_string())?;
+    let mut writer = encoder
+        .write_header()
+        .map_err(|_| "Unable to encode the animation".to_string())?;
+    for frame in frames {
+        let (numer, denom) = frame.delay().numer_denom_ms();
+        let millis = (numer / denom.max(1)).min(u16::MAX as u32) as u16;
+        writer
+            .set_frame_delay(millis, 1000)
+            .and_then(|_| writer.write_image_data(frame.buffer().as_raw()))
+            .map_err(|_| "Unable to encode the animation".to_string())?;
+    }
+    writer
+        .finish()
+        .map_err(|_| "Unable to encode the animation".to_string())?;
+    Ok(buffer)
+}
+
+/// Drops every other frame, the frames that are left are shown for both
+fn halve_frames(frames: Vec<Frame>) -> Vec<Frame> {
+    frames
+        .chunks(2)
+        .map(|pair| {
+            let millis: u32 = pair
+                .iter()
+                .map(|frame| {
+                    let (numer, denom) = frame.delay().numer_denom_ms();
+                    numer / denom.max(1)
+                })
+                .sum();
+            Frame::from_parts(
+                pair[0].buffer().clone(),
+                0,
+                0,
+                Delay::from_numer_denom_ms(millis, 1),
+            )
+        })
+        .collect()
+}
+
+/// Turns any supported image into a 320x320 png sticker, or an apng for animated images.
+/// Animations that are too large lose frames until they fit.
+pub fn prepare_sticker(bytes: &[u8]) -> Result<Vec<u8>, String> {
+    let mut frames = resize_frames(
+        &square_frames(decode_frames(bytes)?),
+        STICKER_SIZE,
+        STICKER_SIZE,
+    );
+    if frames.len() == 1 {
+        let mut data = Vec::new();
+        frames[0]
+            .buffer()
+            .write_to(&mut Cursor::new(&mut data), ImageOutputFormat::Png)
+            .map_err(|_| "Unable to encode the image".to_string())?;
+        if data.len() > MAX_STICKER_BYTES {
+            return Err("The image is too large for a sticker".to_string());
+        }
+        return Ok(data);
+    }
+    loop {
+        let data = encode_apng(&frames)?;
+        if data.len() <= MAX_STICKER_BYTES {
+            return Ok(data);
+        }
+        if frames.len() <= 2 {
+            return Err("The animation is too large even after dropping frames".to_string());
+        }
+        frames = halve_frames(frames);
+    }
+}
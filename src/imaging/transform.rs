@@ -0,0 +1,153 @@
+use std::f32::consts::PI;
+
+use image::codecs::jpeg::JpegEncoder;
+use image::imageops::FilterType;
+use image::{DynamicImage, Frame, Rgba, RgbaImage};
+use imageproc::drawing::{draw_filled_ellipse_mut, draw_polygon_mut};
+use imageproc::geometric_transformations::{rotate_about_center, Interpolation};
+use imageproc::point::Point;
+
+use crate::imaging::emote::{decode_frames, encode_frames, shrink_frames};
+
+const MAX_STILL_SIZE: u32 = 1024;
+const MAX_ANIMATED_SIZE: u32 = 320;
+const TRANSPARENT: Rgba<u8> = Rgba([0, 0, 0, 0]);
+
+pub enum Transform {
+    Deepfry,
+    Jpeg,
+    Invert,
+    /// Size of the blocks in pixels
+    Pixelate(u32),
+    /// Clockwise angle in degrees
+    Rotate(i64),
+    SpeechBubble,
+}
+
+/// Decodes the image, which checks its size and frame count, and scales it down to work with
+fn load_frames(bytes: &[u8]) -> Result<Vec<Frame>, String> {
+    let frames = decode_frames(bytes)?;
+    let max_size = if frames.len() > 1 {
+        MAX_ANIMATED_SIZE
+    } else {
+        MAX_STILL_SIZE
+    };
+    Ok(shrink_frames(frames, max_size))
+}
+
+/// Compresses the image as a jpeg of the quality and reads it back, keeping the transparency
+fn jpeg(image: &RgbaImage, quality: u8) -> RgbaImage {
+    let rgb = DynamicImage::ImageRgba8(image.clone()).into_rgb8();
+    let mut buffer = Vec::new();
+    let decoded = JpegEncoder::new_with_quality(&mut buffer, quality)
+        .encode_image(&rgb)
+        .ok()
+        .and_then(|_| image::load_from_memory(&buffer).ok());
+    let mut decoded = match decoded {
+        Some(decoded) => decoded.into_rgba8(),
+        None => return image.clone(),
+    };
+    for (pixel, original) in decoded.pixels_mut().zip(image.pixels()) {
+        pixel[3] = original[3];
+    }
+    decoded
+}
+
+/// Oversaturated, overcontrasted, sharpened and badly compressed
+fn deepfry(image: &RgbaImage) -> RgbaImage {
+    let mut fried = image::imageops::contrast(image, 60.0);
+    for pixel in fried.pixels_mut() {
+        let luma = (pixel[0] as f32 * 0.3 + pixel[1] as f32 * 0.59 + pixel[2] as f32 * 0.11)
+            .clamp(0.0, 255.0);
+        for channel in 0..3 {
+            // Push the colors away from grey, with a warm tint
+            let tint = [40.0, 0.0, -40.0][channel];
+            let value = luma + (pixel[channel] as f32 - luma) * 2.5 + tint;
+            pixel[channel] = value.clamp(0.0, 255.0) as u8;
+        }
+    }
+    let fried = image::imageops::unsharpen(&fried, 2.0, 2);
+    jpeg(&fried, 8)
+}
+
+fn pixelate(image: &RgbaImage, block: u32) -> RgbaImage {
+    let (width, height) = image.dimensions();
+    let small = image::imageops::resize(
+        image,
+        (width / block).max(1),
+        (height / block).max(1),
+        FilterType::Triangle,
+    );
+    image::imageops::resize(&small, width, height, FilterType::Nearest)
+}
+
+fn rotate(image: &RgbaImage, degrees: i64) -> RgbaImage {
+    match degrees.rem_euclid(360) {
+        0 => image.clone(),
+        90 => image::imageops::rotate90(image),
+        180 => image::imageops::rotate180(image),
+        270 => image::imageops::rotate270(image),
+        degrees => {
+            // Pad to the diagonal first so no corners are cut off
+            let (width, height) = image.dimensions();
+            let side = ((width * width + height * height) as f32).sqrt().ceil() as u32;
+            let mut padded = RgbaImage::new(side, side);
+            image::imageops::overlay(
+                &mut padded,
+                image,
+                ((side - width) / 2).into(),
+                ((side - height) / 2).into(),
+            );
+            let theta = degrees as f32 * PI / 180.0;
+            rotate_about_center(&padded, theta, Interpolation::Bilinear, TRANSPARENT)
+        }
+    }
+}
+
+/// Cuts a transparent speech bubble out of the top of the image
+fn speech_bubble(image: &RgbaImage) -> RgbaImage {
+    let mut image = image.clone();
+    let (width, height) = (image.width() as i32, image.height() as i32);
+    draw_filled_ellipse_mut(
+        &mut image,
+        (width / 2, 0),
+        width * 3 / 4,
+        height / 5,
+        TRANSPARENT,
+    );
+    let tail = [
+        Point::new(width * 3 / 10, height / 6),
+        Point::new(width / 2, height / 6),
+        Point::new(width / 4, height * 2 / 5),
+    ];
+    draw_polygon_mut(&mut image, &tail, TRANSPARENT);
+    image
+}
+
+impl Transform {
+    fn apply(&self, image: &RgbaImage) -> RgbaImage {
+        match self {
+            Transform::Deepfry => deepfry(image),
+            Transform::Jpeg => jpeg(image, 5),
+            Transform::Invert => {
+                let mut image = image.clone();
+                image::imageops::invert(&mut image);
+                image
+            }
+            Transform::Pixelate(block) => pixelate(image, (*block).max(1)),
+            Transform::Rotate(degrees) => rotate(image, *degrees),
+            Transform::SpeechBubble => speech_bubble(image),
+        }
+    }
+}
+
+/// Applies the transform to every frame of the image.
+pub fn transform_image(bytes: &[u8], transform: Transform) -> Result<(Vec<u8>, bool), String> {
+    let frames = load_frames(bytes)?;
+    let animated = frames.len() > 1;
+    let frames = frames
+        .into_iter()
+        .map(|frame| Frame::from_parts(transform.apply(frame.buffer()), 0, 0, frame.delay()))
+        .collect();
+    Ok((encode_frames(frames)?, animated))
+}
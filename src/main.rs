@@ -1,20 +1,32 @@
 mod commands;
 mod friday;
+mod imaging;
+mod store;
 
+use std::collections::HashMap;
 use std::env;
 
-use commands::handler::{autocomplete_handler, command_handler};
+use commands::handler::{autocomplete_handler, command_handler, component_handler};
 use friday::friday::handle_message;
 use serenity::async_trait;
 use serenity::model::application::command::Command;
 use serenity::model::application::interaction::Interaction;
 use serenity::model::gateway::Ready;
-use serenity::model::prelude::Message;
+use serenity::model::prelude::{
+    Emoji, EmojiId, GuildId, Message, Reaction, ReactionType, Sticker, StickerId,
+};
 use serenity::prelude::*;
 use std::sync::atomic::{AtomicBool, Ordering};
 use rustrict::CensorStr;
 
+use crate::commands::emotes::sync::{reconcile, reconcile_guild};
+use crate::commands::reaction_roles::{reaction_changed, sync_reaction_roles};
+use crate::commands::starboard;
+use crate::commands::sticker;
+use crate::commands::usage::{record_message, record_reaction, BOT_ID};
+use crate::friday::calendar::calendar_controller;
 use crate::friday::friday::friday_controller;
+use crate::store::{flush_stores, init_stores};
 
 static THREAD: AtomicBool = AtomicBool::new(false);
 
@@ -32,6 +44,9 @@ impl EventHandler for Handler {
             Interaction::Autocomplete(autocomplete) => {
                 autocomplete_handler(&ctx, &autocomplete).await;
             }
+            Interaction::MessageComponent(component) => {
+                component_handler(&ctx, component).await;
+            }
             _ => {
                 println!("Received interaction: {:#?}, not implemented!", interaction);
             }
@@ -40,6 +55,7 @@ impl EventHandler for Handler {
 
     async fn message(&self, ctx: Context, msg: Message) {
         println!("Received message: {:#?}", msg);
+        record_message(msg.author.id.0, &msg.content).await;
         if msg.content.is_inappropriate() {
             msg.reply(&ctx, GIF).await.unwrap();
         }
@@ -49,13 +65,55 @@ impl EventHandler for Handler {
         handle_message(&msg, &ctx).await;
     }
 
+    async fn reaction_add(&self, ctx: Context, reaction: Reaction) {
+        if let (ReactionType::Custom { id, .. }, Some(user)) = (&reaction.emoji, reaction.user_id) {
+            record_reaction(user.0, &id.to_string()).await;
+        }
+        reaction_changed(&ctx, &reaction, true).await;
+        starboard::reaction_changed(&ctx, &reaction).await;
+    }
+
+    async fn reaction_remove(&self, ctx: Context, reaction: Reaction) {
+        reaction_changed(&ctx, &reaction, false).await;
+        starboard::reaction_changed(&ctx, &reaction).await;
+    }
+
+    async fn guild_emojis_update(
+        &self,
+        _ctx: Context,
+        guild_id: GuildId,
+        current_state: HashMap<EmojiId, Emoji>,
+    ) {
+        reconcile_guild(guild_id, current_state.into_values().collect()).await;
+    }
+
+    async fn guild_stickers_update(
+        &self,
+        _ctx: Context,
+        guild_id: GuildId,
+        current_state: HashMap<StickerId, Sticker>,
+    ) {
+        sticker::reconcile_guild(guild_id, current_state.into_values().collect()).await;
+    }
+
     async fn ready(&self, ctx: Context, ready: Ready) {
         println!("{} is connected!", ready.user.name);
+        BOT_ID.store(ready.user.id.0, Ordering::Relaxed);
         let guild_command = Command::set_global_application_commands(&ctx.http, |commands| {
             commands
                 .create_application_command(|command| commands::react::register(command))
+                .create_application_command(|command| commands::react_with::register(command))
                 .create_application_command(|command| commands::create_reaction::register(command))
+                .create_application_command(|command| commands::delete_reaction::register(command))
+                .create_application_command(|command| commands::emotes::register(command))
                 .create_application_command(|command| commands::pardon::register(command))
+                .create_application_command(|command| commands::friday::register(command))
+                .create_application_command(|command| commands::calendar::register(command))
+                .create_application_command(|command| commands::reaction_roles::register(command))
+                .create_application_command(|command| commands::starboard::register(command))
+                .create_application_command(|command| commands::sticker::register(command))
+                .create_application_command(|command| commands::meme::register(command))
+                .create_application_command(|command| commands::image::register(command))
         })
         .await;
         if std::env::args().any(|arg| arg == "--DFriday".to_string()) {
@@ -65,9 +123,21 @@ impl EventHandler for Handler {
             return;
         }
         THREAD.store(true, Ordering::Relaxed);
+        // Catch up on what changed while the bot was offline
+        let sync_ctx = ctx.clone();
+        let guilds: Vec<GuildId> = ready.guilds.iter().map(|guild| guild.id).collect();
+        tokio::spawn(async move {
+            let diff = reconcile(&sync_ctx, false).await;
+            if !diff.is_empty() {
+                println!("Synced emotes:\n{}", diff.describe());
+            }
+            sticker::reconcile(&sync_ctx, &guilds).await;
+            sync_reaction_roles(&sync_ctx).await;
+        });
         tokio::spawn(async move {
             loop {
                 friday_controller(&ctx).await;
+                calendar_controller(&ctx).await;
                 tokio::time::sleep(std::time::Duration::from_secs(60)).await;
             }
         });
@@ -75,13 +145,40 @@ impl EventHandler for Handler {
     }
 }
 
+/// Resolves on ctrl-c, or when the service manager asks the bot to stop
+async fn shutdown_signal() {
+    #[cfg(unix)]
+    {
+        use tokio::signal::unix::{signal, SignalKind};
+        let mut terminate = signal(SignalKind::terminate()).expect("Unable to listen for SIGTERM");
+        tokio::select! {
+            _ = tokio::signal::ctrl_c() => {}
+            _ = terminate.recv() => {}
+        }
+    }
+    #[cfg(not(unix))]
+    {
+        let _ = tokio::signal::ctrl_c().await;
+    }
+}
+
 #[tokio::main]
 async fn main() {
+    init_stores().await;
+    // Persist changes in every mode, including --DFriday
+    tokio::spawn(async move {
+        loop {
+            flush_stores().await;
+            tokio::time::sleep(std::time::Duration::from_secs(5)).await;
+        }
+    });
     // Configure the client with your Discord bot token in the environment.
     let token = env::args().nth(1).expect("Expected a bot token");
     let intents = GatewayIntents::GUILD_MESSAGES
         | GatewayIntents::DIRECT_MESSAGES
-        | GatewayIntents::MESSAGE_CONTENT;
+        | GatewayIntents::MESSAGE_CONTENT
+        | GatewayIntents::GUILD_EMOJIS_AND_STICKERS
+        | GatewayIntents::GUILD_MESSAGE_REACTIONS;
     // Build our client.
     let mut client = Client::builder(&token, intents)
         .event_handler(Handler)
@@ -92,7 +189,15 @@ async fn main() {
     //
     // Shards will automatically attempt to reconnect, and will perform
     // exponential backoff until it reconnects.
-    if let Err(why) = client.start().await {
-        println!("Client error: {:?}", why);
+    tokio::select! {
+        result = client.start() => {
+            if let Err(why) = result {
+                println!("Client error: {:?}", why);
+            }
+        }
+        _ = shutdown_signal() => {
+            println!("Shutting down");
+        }
     }
+    flush_stores().await;
 }
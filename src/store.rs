@@ -0,0 +1,102 @@
+use std::fs::File;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use serde::{de::DeserializeOwned, Serialize};
+use tokio::sync::{RwLock, RwLockReadGuard};
+
+use crate::commands::emotes::proposals::PROPOSALS;
+use crate::commands::emotes::storage::STORAGE_GUILDS;
+use crate::commands::pardon::PARDON_LOG;
+use crate::commands::react::EMOTES;
+use crate::commands::reaction_roles::PANELS;
+use crate::commands::starboard::STARBOARD;
+use crate::commands::sticker::STICKERS;
+use crate::commands::usage::USAGE;
+use crate::friday::calendar::{reload_holidays, CALENDAR, CALENDAR_STATE};
+use crate::friday::config::CONFIG;
+use crate::friday::friday::FRIDAY;
+
+/// State kept in memory and written back to its json file by `flush`,
+/// so reading it never touches the disk
+pub struct Store<T> {
+    path: &'static str,
+    data: RwLock<T>,
+    dirty: AtomicBool,
+}
+
+impl<T> Store<T>
+where
+    T: Serialize + DeserializeOwned + Default,
+{
+    /// Loads the file, falling back to the default which is written on the next flush
+    pub fn load(path: &'static str) -> Self {
+        let (data, dirty) = match File::open(path) {
+            Ok(f) => (
+                serde_json::from_reader(&f).expect("Unable to read file"),
+                false,
+            ),
+            Err(_) => (T::default(), true),
+        };
+        Self {
+            path,
+            data: RwLock::new(data),
+            dirty: AtomicBool::new(dirty),
+        }
+    }
+
+    pub async fn read(&self) -> RwLockReadGuard<'_, T> {
+        self.data.read().await
+    }
+
+    /// Changes the state, it is persisted on the next flush
+    pub async fn update<R>(&self, f: impl FnOnce(&mut T) -> R) -> R {
+        let mut data = self.data.write().await;
+        let result = f(&mut data);
+        self.dirty.store(true, Ordering::Release);
+        result
+    }
+
+    /// Writes the state to disk if it changed since the last flush
+    pub async fn flush(&self) {
+        if !self.dirty.swap(false, Ordering::AcqRel) {
+            return;
+        }
+        let contents = serde_json::to_string_pretty(&*self.data.read().await).unwrap();
+        if let Err(why) = tokio::fs::write(self.path, contents).await {
+            println!("Unable to write {}: {}", self.path, why);
+            self.dirty.store(true, Ordering::Release);
+        }
+    }
+}
+
+/// Loads every store up front so the first message doesn't hit the disk
+pub async fn init_stores() {
+    lazy_static::initialize(&FRIDAY);
+    lazy_static::initialize(&CONFIG);
+    lazy_static::initialize(&CALENDAR);
+    lazy_static::initialize(&CALENDAR_STATE);
+    lazy_static::initialize(&EMOTES);
+    lazy_static::initialize(&USAGE);
+    lazy_static::initialize(&PANELS);
+    lazy_static::initialize(&STARBOARD);
+    lazy_static::initialize(&PROPOSALS);
+    lazy_static::initialize(&STICKERS);
+    lazy_static::initialize(&STORAGE_GUILDS);
+    lazy_static::initialize(&PARDON_LOG);
+    reload_holidays().await;
+}
+
+pub async fn flush_stores() {
+    FRIDAY.flush().await;
+    CONFIG.flush().await;
+    CALENDAR.flush().await;
+    CALENDAR_STATE.flush().await;
+    EMOTES.flush().await;
+    USAGE.flush().await;
+    PANELS.flush().await;
+    STARBOARD.flush().await;
+    PROPOSALS.flush().await;
+    STICKERS.flush().await;
+    STORAGE_GUILDS.flush().await;
+    PARDON_LOG.flush().await;
+}